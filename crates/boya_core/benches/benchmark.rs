@@ -11,7 +11,7 @@ fn init_gba() -> Gba {
     let mut gba = Gba::default();
 
     gba.boot();
-    gba.load_rom(ROM);
+    gba.load_rom(ROM).unwrap();
     gba.skip_bios();
 
     gba
@@ -1,33 +1,162 @@
-use crate::{apu::registers::ApuRegister, bus::registers::dma::DmaTimer, utils::Reset};
+use crate::{
+    apu::{registers::ApuRegister, resampler::Resampler},
+    bus::registers::dma::DmaTimer,
+    utils::{Reset, savestate::impl_savestate},
+};
 
 pub mod registers;
+pub mod resampler;
+
+/// The frame sequencer is clocked at 512 Hz
+pub const FRAME_SEQUENCER_CYCLES: u32 = 0x8000;
+/// The mixer output is sampled at 32.768 kHz
+pub const SAMPLE_CYCLES: u32 = 0x200;
 
 #[derive(Debug, Default)]
 pub struct Apu {
     pub registers: ApuRegister,
+    pub resampler: Resampler,
 
     fifo_a_request: bool,
     fifo_b_request: bool,
+    sequencer_divider: u32,
+    sequencer_step: u8,
+    sample_divider: u32,
 }
 
+impl_savestate!(Apu {
+    registers,
+    fifo_a_request,
+    fifo_b_request,
+    sequencer_divider,
+    sequencer_step,
+    sample_divider
+});
+
 impl Apu {
-    pub fn step(&mut self) {}
+    pub fn tick(&mut self, cycles: u32) {
+        self.sample_divider += cycles;
+
+        while self.sample_divider >= SAMPLE_CYCLES {
+            let (left, right) = self.sample();
+
+            // scale the 10-bit DAC range up to i16
+            self.resampler.push((left << 6, right << 6));
+            self.sample_divider -= SAMPLE_CYCLES;
+        }
+
+        if !self.registers.soundcnt.master_enable() {
+            return;
+        }
+
+        self.sequencer_divider += cycles;
+
+        while self.sequencer_divider >= FRAME_SEQUENCER_CYCLES {
+            self.step_sequencer();
+            self.sequencer_divider -= FRAME_SEQUENCER_CYCLES;
+        }
+
+        self.registers.square1.tick(cycles);
+        self.registers.square2.tick(cycles);
+        self.registers.wave.tick(cycles);
+        self.registers.noise.tick(cycles);
+    }
+
+    /// Mixed output of the four PSG channels, before bias.
+    pub fn psg_sample(&self) -> (i16, i16) {
+        let soundcnt = &self.registers.soundcnt;
+
+        let outputs = [
+            self.registers.square1.output(),
+            self.registers.square2.output(),
+            self.registers.wave.output(),
+            self.registers.noise.output(),
+        ];
+
+        let mut left = 0;
+        let mut right = 0;
+
+        for (channel, output) in outputs.into_iter().enumerate() {
+            if soundcnt.psg_enable_left(channel) {
+                left += output as i16;
+            }
+
+            if soundcnt.psg_enable_right(channel) {
+                right += output as i16;
+            }
+        }
+
+        let left = left * (soundcnt.psg_volume_left() as i16 + 1);
+        let right = right * (soundcnt.psg_volume_right() as i16 + 1);
+        let shift = soundcnt.psg_volume_shift();
+
+        (left >> shift, right >> shift)
+    }
+
+    /// Mixed output of the two Direct Sound channels, before bias.
+    pub fn fifo_sample(&self) -> (i16, i16) {
+        let soundcnt = &self.registers.soundcnt;
+
+        let a = (self.registers.fifo_a.sample() as i16) << soundcnt.fifo_a_volume_shift();
+        let b = (self.registers.fifo_b.sample() as i16) << soundcnt.fifo_b_volume_shift();
+
+        let mut left = 0;
+        let mut right = 0;
+
+        if soundcnt.fifo_a_enable_left() {
+            left += a;
+        }
+
+        if soundcnt.fifo_a_enable_right() {
+            right += a;
+        }
+
+        if soundcnt.fifo_b_enable_left() {
+            left += b;
+        }
+
+        if soundcnt.fifo_b_enable_right() {
+            right += b;
+        }
+
+        (left, right)
+    }
+
+    /// Final stereo output, biased and clipped through SOUNDBIAS then centered around zero.
+    pub fn sample(&self) -> (i16, i16) {
+        if !self.registers.soundcnt.master_enable() {
+            return (0, 0);
+        }
+
+        let (psg_left, psg_right) = self.psg_sample();
+        let (fifo_left, fifo_right) = self.fifo_sample();
+        let bias = &self.registers.bias;
+
+        (
+            bias.apply(psg_left + fifo_left),
+            bias.apply(psg_right + fifo_right),
+        )
+    }
 
     pub fn on_timer_overflow(&mut self, timer: DmaTimer) {
+        if !self.registers.soundcnt.master_enable() {
+            return;
+        }
+
         if self.registers.soundcnt.timer_select_a() == timer {
+            self.registers.fifo_a.latch_sample();
+
             if self.registers.fifo_a.needs_samples() {
                 self.fifo_a_request = true;
             }
-
-            self.registers.fifo_a.buffer.pop(); // TODO: implementation
         }
 
         if self.registers.soundcnt.timer_select_b() == timer {
+            self.registers.fifo_b.latch_sample();
+
             if self.registers.fifo_b.needs_samples() {
                 self.fifo_b_request = true;
             }
-
-            self.registers.fifo_b.buffer.pop(); // TODO: implementation
         }
     }
 
@@ -38,6 +167,29 @@ impl Apu {
     pub fn poll_fifo_b_request(&mut self) -> bool {
         std::mem::replace(&mut self.fifo_b_request, false)
     }
+
+    fn step_sequencer(&mut self) {
+        let registers = &mut self.registers;
+
+        if self.sequencer_step % 2 == 0 {
+            registers.square1.clock_length();
+            registers.square2.clock_length();
+            registers.wave.clock_length();
+            registers.noise.clock_length();
+        }
+
+        if self.sequencer_step % 4 == 2 {
+            registers.square1.clock_sweep();
+        }
+
+        if self.sequencer_step == 7 {
+            registers.square1.clock_envelope();
+            registers.square2.clock_envelope();
+            registers.noise.clock_envelope();
+        }
+
+        self.sequencer_step = (self.sequencer_step + 1) % 8;
+    }
 }
 
 impl Reset for Apu {
@@ -45,5 +197,82 @@ impl Reset for Apu {
         self.registers = ApuRegister::default();
         self.fifo_a_request = false;
         self.fifo_b_request = false;
+        self.sequencer_divider = 0;
+        self.sequencer_step = 0;
+        self.sample_divider = 0;
+        self.resampler.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apu::Apu,
+        bus::{Bus, registers::dma::DmaTimer},
+    };
+
+    #[test]
+    fn test_psg_channels() {
+        let mut apu = Apu::default();
+        let registers = &mut apu.registers;
+
+        registers.write_byte(0x0400_0084, 0x80); // master enable
+        registers.write_hword(0x0400_0080, 0xFF77); // all channels, max volume
+        registers.write_hword(0x0400_0082, 0x0002); // PSG 100%
+
+        registers.write_hword(0x0400_0062, 0xF080 | 62); // 50% duty, volume 15, length 2
+        registers.write_hword(0x0400_0064, 0xC000 | 2040); // trigger with length enable
+
+        registers.write_hword(0x0400_0078, 0xF000); // volume 15
+        registers.write_hword(0x0400_007C, 0x8000); // trigger
+
+        assert_eq!(apu.registers.read_byte(0x0400_0084), 0x89);
+
+        let mut high = 0;
+
+        for _ in 0..16 {
+            apu.tick(128);
+
+            if apu.registers.square1.output() == 15 {
+                high += 1;
+            }
+        }
+
+        assert_eq!(high, 8, "square 1 should be high half of the time");
+        assert!(apu.psg_sample().0 > 0, "PSG output");
+
+        apu.tick(super::FRAME_SEQUENCER_CYCLES * 4);
+
+        assert!(!apu.registers.square1.enabled(), "length counter expired");
+        assert!(apu.registers.noise.enabled(), "no length counter");
+    }
+
+    #[test]
+    fn test_fifo_playback() {
+        let mut apu = Apu::default();
+        let registers = &mut apu.registers;
+
+        registers.write_byte(0x0400_0084, 0x80); // master enable
+        registers.write_hword(0x0400_0082, 0x2304); // A: 100% R/L timer 0, B: 50% L timer 0
+        registers.write_word(0x0400_00A0, 0x0000_8040); // 0x40, -0x80
+        registers.write_word(0x0400_00A4, 0x0000_0010);
+
+        apu.on_timer_overflow(DmaTimer::Timer0);
+
+        assert_eq!(apu.fifo_sample(), (0x100 + 0x20, 0x100));
+        assert_eq!(apu.sample(), (0x120, 0x100));
+        assert!(apu.poll_fifo_a_request(), "FIFO A DMA request");
+        assert!(apu.poll_fifo_b_request(), "FIFO B DMA request");
+
+        apu.on_timer_overflow(DmaTimer::Timer0);
+        apu.on_timer_overflow(DmaTimer::Timer1);
+
+        assert_eq!(apu.fifo_sample(), (-0x200, -0x200));
+        assert_eq!(apu.sample(), (-0x200, -0x200), "clipped to the DAC range");
+
+        apu.registers.write_hword(0x0400_0082, 0xA304); // reset FIFO B
+
+        assert_eq!(apu.registers.fifo_a.buffer.len(), 2);
+        assert!(apu.registers.fifo_b.buffer.is_empty());
     }
 }
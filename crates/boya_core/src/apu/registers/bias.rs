@@ -1,10 +1,34 @@
+use crate::utils::{bitflags::Bitflag, savestate::impl_savestate};
+
 #[derive(Debug)]
 pub struct Bias {
     pub value: u16,
 }
 
+impl_savestate!(Bias { value });
+
 impl Default for Bias {
     fn default() -> Self {
         Self { value: 0x200 }
     }
 }
+
+impl Bias {
+    pub fn level(&self) -> i16 {
+        (self.value.get_bits(1, 9) << 1) as i16
+    }
+
+    /// 9bit, 8bit, 7bit or 6bit output
+    pub fn amplitude_resolution(&self) -> u16 {
+        9 - self.value.get_bits(14, 15)
+    }
+
+    /// Adds the bias level and clips the sample to the 10-bit DAC range,
+    /// the result is centered back around zero.
+    pub fn apply(&self, sample: i16) -> i16 {
+        let value = (sample + self.level()).clamp(0, 0x3FF);
+        let mask = !((1 << (10 - self.amplitude_resolution())) - 1);
+
+        (value & mask) - 0x200
+    }
+}
@@ -0,0 +1,48 @@
+use crate::utils::{bitflags::Bitflag, savestate::impl_savestate};
+
+/// Volume envelope shared by the square and noise channels.
+///
+/// The control byte is the upper half of the channel's envelope register:
+/// step time (0-2), direction (3) and initial volume (4-7).
+#[derive(Debug, Default)]
+pub struct Envelope {
+    volume: u8,
+    timer: u8,
+}
+
+impl_savestate!(Envelope { volume, timer });
+
+impl Envelope {
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn dac_enable(cnt: u8) -> bool {
+        cnt.get_bits(3, 7) != 0
+    }
+
+    pub fn reload(&mut self, cnt: u8) {
+        self.volume = cnt.get_bits(4, 7);
+        self.timer = cnt.get_bits(0, 2);
+    }
+
+    pub fn clock(&mut self, cnt: u8) {
+        let step_time = cnt.get_bits(0, 2);
+
+        if step_time == 0 {
+            return;
+        }
+
+        self.timer = self.timer.saturating_sub(1);
+
+        if self.timer == 0 {
+            self.timer = step_time;
+
+            match cnt.has(3) {
+                true if self.volume < 15 => self.volume += 1,
+                false if self.volume > 0 => self.volume -= 1,
+                _ => {}
+            }
+        }
+    }
+}
@@ -1,13 +1,38 @@
-use crate::{bus::Bus, utils::collections::FifoBuffer};
+use crate::{
+    bus::Bus,
+    utils::{collections::FifoBuffer, savestate::impl_savestate},
+};
+
+pub const FIFO_SIZE: usize = 32;
 
 #[derive(Debug, Default)]
 pub struct Fifo {
-    pub buffer: FifoBuffer<u8, 8>,
+    pub buffer: FifoBuffer<i8, FIFO_SIZE>,
+
+    latch: i8,
 }
 
+impl_savestate!(Fifo { buffer, latch });
+
 impl Fifo {
+    /// A DMA request is sent when the FIFO is half empty (16 bytes or less)
     pub fn needs_samples(&self) -> bool {
-        self.buffer.len() > 0 && self.buffer.len() <= 4
+        self.buffer.len() <= FIFO_SIZE / 2
+    }
+
+    pub fn sample(&self) -> i8 {
+        self.latch
+    }
+
+    pub fn latch_sample(&mut self) {
+        if let Some(sample) = self.buffer.pop() {
+            self.latch = sample;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.latch = 0;
     }
 }
 
@@ -17,6 +42,6 @@ impl Bus for Fifo {
     }
 
     fn write_byte(&mut self, _address: u32, value: u8) {
-        self.buffer.push(value);
+        self.buffer.push(value as i8);
     }
 }
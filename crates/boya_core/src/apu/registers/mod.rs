@@ -1,36 +1,95 @@
 pub mod bias;
+pub mod envelope;
 pub mod fifo;
+pub mod noise;
 pub mod soundcnt;
+pub mod square;
+pub mod wave;
 
 use crate::{
-    apu::registers::{bias::Bias, fifo::Fifo, soundcnt::Soundcnt},
+    apu::registers::{
+        bias::Bias, fifo::Fifo, noise::Noise, soundcnt::Soundcnt, square::Square, wave::Wave,
+    },
     bus::Bus,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
 };
 
 #[derive(Debug, Default)]
 pub struct ApuRegister {
-    /// 0x080: Sound Bias (R/W)
+    /// 0x060: Channel 1 Sweep, Duty/Length/Envelope, Frequency/Control (R/W)
+    pub square1: Square,
+    /// 0x068: Channel 2 Duty/Length/Envelope, Frequency/Control (R/W)
+    pub square2: Square,
+    /// 0x070: Channel 3 Stop/Wave RAM select, Length/Volume, Frequency/Control (R/W)
+    pub wave: Wave,
+    /// 0x078: Channel 4 Length/Envelope, Frequency/Control (R/W)
+    pub noise: Noise,
+    /// 0x080: Sound Control (R/W)
     pub soundcnt: Soundcnt,
     /// 0x088: Sound Bias (R/W)
     pub bias: Bias,
     /// 0x0A0: Sound FIFO A (W)
     pub fifo_a: Fifo,
-    /// 0x0A4: Sound FIFO A (W)
+    /// 0x0A4: Sound FIFO B (W)
     pub fifo_b: Fifo,
 }
 
+impl_savestate!(ApuRegister {
+    square1,
+    square2,
+    wave,
+    noise,
+    soundcnt,
+    bias,
+    fifo_a,
+    fifo_b
+});
+
 impl Bus for ApuRegister {
     fn read_byte(&self, address: u32) -> u8 {
         match address % 0x0400_0000 {
+            0x060..=0x061 => (self.square1.cnt_l & 0x007F).read_byte(address),
+            0x062..=0x063 => (self.square1.cnt_h & 0xFFC0).read_byte(address),
+            0x064..=0x065 => (self.square1.cnt_x & 0x4000).read_byte(address),
+            0x068..=0x069 => (self.square2.cnt_h & 0xFFC0).read_byte(address),
+            0x06C..=0x06D => (self.square2.cnt_x & 0x4000).read_byte(address),
+            0x070..=0x071 => (self.wave.cnt_l & 0x00E0).read_byte(address),
+            0x072..=0x073 => (self.wave.cnt_h & 0xE000).read_byte(address),
+            0x074..=0x075 => (self.wave.cnt_x & 0x4000).read_byte(address),
+            0x078..=0x079 => (self.noise.cnt_l & 0xFF00).read_byte(address),
+            0x07C..=0x07D => (self.noise.cnt_h & 0x40FF).read_byte(address),
+            0x080..=0x081 => (self.soundcnt.cnt_l & 0xFF77).read_byte(address),
+            0x082..=0x083 => (self.soundcnt.cnt_h & 0x770F).read_byte(address),
+            0x084 => self.read_soundcnt_x(),
             0x088..=0x089 => self.bias.value.read_byte(address),
+            0x090..=0x09F => self.wave.read_ram(address),
             _ => 0,
         }
     }
 
     fn write_byte(&mut self, address: u32, value: u8) {
-        match address % 0x0400_0000 {
+        let address = address % 0x0400_0000;
+
+        // PSG registers are read-only while the sound circuit is disabled
+        if !self.soundcnt.master_enable() && (0x060..=0x081).contains(&address) {
+            return;
+        }
+
+        match address {
+            0x060..=0x061 => self.square1.cnt_l.write_byte(address, value),
+            0x062..=0x063 => self.square1.write_cnt_h(address, value),
+            0x064..=0x065 => self.square1.write_cnt_x(address, value),
+            0x068..=0x069 => self.square2.write_cnt_h(address, value),
+            0x06C..=0x06D => self.square2.write_cnt_x(address, value),
+            0x070..=0x071 => self.wave.write_cnt_l(address, value),
+            0x072..=0x073 => self.wave.write_cnt_h(address, value),
+            0x074..=0x075 => self.wave.write_cnt_x(address, value),
+            0x078..=0x079 => self.noise.write_cnt_l(address, value),
+            0x07C..=0x07D => self.noise.write_cnt_h(address, value),
             0x080..=0x083 => self.write_soundcnt(address, value),
+            0x084 => self.write_soundcnt_x(value),
             0x088..=0x089 => self.bias.value.write_byte(address, value),
+            0x090..=0x09F => self.wave.write_ram(address, value),
             0x0A0..=0x0A3 => self.fifo_a.write_byte(address, value),
             0x0A4..=0x0A7 => self.fifo_b.write_byte(address, value),
             _ => {}
@@ -43,11 +102,36 @@ impl ApuRegister {
         self.soundcnt.write_byte(address, value);
 
         if self.soundcnt.reset_fifo_a() {
-            self.fifo_a.buffer.clear();
+            self.fifo_a.reset();
         }
 
         if self.soundcnt.reset_fifo_b() {
-            self.fifo_b.buffer.clear();
+            self.fifo_b.reset();
+        }
+    }
+
+    fn read_soundcnt_x(&self) -> u8 {
+        let mut value = (self.soundcnt.cnt_x & 0x80) as u8;
+
+        value.update(0, self.square1.enabled());
+        value.update(1, self.square2.enabled());
+        value.update(2, self.wave.enabled());
+        value.update(3, self.noise.enabled());
+        value
+    }
+
+    fn write_soundcnt_x(&mut self, value: u8) {
+        self.soundcnt.cnt_x = (value & 0x80).into();
+
+        if !self.soundcnt.master_enable() {
+            let wave_ram = self.wave.ram;
+
+            self.square1 = Square::default();
+            self.square2 = Square::default();
+            self.wave = Wave::default();
+            self.wave.ram = wave_ram;
+            self.noise = Noise::default();
+            self.soundcnt.cnt_l = 0;
         }
     }
 }
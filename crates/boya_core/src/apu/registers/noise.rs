@@ -0,0 +1,142 @@
+use crate::{
+    apu::registers::envelope::Envelope,
+    bus::Bus,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
+
+/// Noise channel, outputs the carry of a 15-bit or 7-bit LFSR.
+#[derive(Debug, Default)]
+pub struct Noise {
+    /// Length/Envelope (R/W)
+    pub cnt_l: u16,
+    /// Frequency/Control (R/W)
+    pub cnt_h: u16,
+
+    enabled: bool,
+    divider: u32,
+    lfsr: u16,
+    carry: bool,
+    length: u16,
+    envelope: Envelope,
+}
+
+impl_savestate!(Noise {
+    cnt_l,
+    cnt_h,
+    enabled,
+    divider,
+    lfsr,
+    carry,
+    length,
+    envelope
+});
+
+impl Noise {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn length_load(&self) -> u16 {
+        self.cnt_l.get_bits(0, 5)
+    }
+
+    pub fn envelope_cnt(&self) -> u8 {
+        self.cnt_l.get_bits_u8(8, 15)
+    }
+
+    pub fn dividing_ratio(&self) -> u32 {
+        match self.cnt_h.get_bits(0, 2) {
+            0 => 8,
+            r => r as u32 * 16,
+        }
+    }
+
+    pub fn short_width(&self) -> bool {
+        self.cnt_h.has(3)
+    }
+
+    pub fn shift_frequency(&self) -> u16 {
+        self.cnt_h.get_bits(4, 7)
+    }
+
+    pub fn length_enable(&self) -> bool {
+        self.cnt_h.has(14)
+    }
+
+    pub fn output(&self) -> u8 {
+        if self.enabled && self.carry {
+            self.envelope.volume()
+        } else {
+            0
+        }
+    }
+
+    pub fn tick(&mut self, cycles: u32) {
+        if !self.enabled {
+            return;
+        }
+
+        // 4.194304 MHz based divider on the GB, the GBA clock runs 4 times faster
+        let period = (self.dividing_ratio() << self.shift_frequency()) * 4;
+
+        self.divider += cycles;
+
+        while self.divider >= period {
+            self.shift();
+            self.divider -= period;
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length_enable() && self.length > 0 {
+            self.length -= 1;
+
+            if self.length == 0 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock(self.envelope_cnt());
+    }
+
+    pub fn write_cnt_l(&mut self, address: u32, value: u8) {
+        self.cnt_l.write_byte(address, value);
+
+        if address % 2 == 0 {
+            self.length = 64 - self.length_load();
+        } else if !Envelope::dac_enable(value) {
+            self.enabled = false;
+        }
+    }
+
+    pub fn write_cnt_h(&mut self, address: u32, value: u8) {
+        self.cnt_h.write_byte(address, value);
+
+        if self.cnt_h.take(15) {
+            self.trigger();
+        }
+    }
+
+    fn shift(&mut self) {
+        self.carry = self.lfsr.has(0);
+        self.lfsr >>= 1;
+
+        if self.carry {
+            self.lfsr ^= if self.short_width() { 0x60 } else { 0x6000 };
+        }
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = Envelope::dac_enable(self.envelope_cnt());
+        self.divider = 0;
+        self.carry = false;
+        self.lfsr = if self.short_width() { 0x40 } else { 0x4000 };
+        self.envelope.reload(self.envelope_cnt());
+
+        if self.length == 0 {
+            self.length = 64;
+        }
+    }
+}
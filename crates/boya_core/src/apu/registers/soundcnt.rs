@@ -1,15 +1,63 @@
 use crate::{
     bus::{Bus, registers::dma::DmaTimer},
-    utils::bitflags::Bitflag,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
 };
 
 #[derive(Debug, Default)]
 pub struct Soundcnt {
     pub cnt_l: u16,
     pub cnt_h: u16,
+    pub cnt_x: u16,
 }
 
+impl_savestate!(Soundcnt {
+    cnt_l,
+    cnt_h,
+    cnt_x
+});
+
 impl Soundcnt {
+    pub fn psg_volume_right(&self) -> u16 {
+        self.cnt_l.get_bits(0, 2)
+    }
+
+    pub fn psg_volume_left(&self) -> u16 {
+        self.cnt_l.get_bits(4, 6)
+    }
+
+    pub fn psg_enable_right(&self, channel: usize) -> bool {
+        self.cnt_l.has(8 + channel as u16)
+    }
+
+    pub fn psg_enable_left(&self, channel: usize) -> bool {
+        self.cnt_l.has(12 + channel as u16)
+    }
+
+    /// Right shift applied to the PSG mix (25%, 50%, 100%)
+    pub fn psg_volume_shift(&self) -> u16 {
+        match self.cnt_h.get_bits(0, 1) {
+            0 => 2,
+            1 => 1,
+            _ => 0,
+        }
+    }
+
+    /// Left shift applied to the 8-bit FIFO A samples (50%, 100%)
+    pub fn fifo_a_volume_shift(&self) -> u16 {
+        match self.cnt_h.get(2) {
+            0 => 1,
+            _ => 2,
+        }
+    }
+
+    pub fn fifo_a_enable_right(&self) -> bool {
+        self.cnt_h.has(8)
+    }
+
+    pub fn fifo_a_enable_left(&self) -> bool {
+        self.cnt_h.has(9)
+    }
+
     pub fn timer_select_a(&self) -> DmaTimer {
         match self.cnt_h.get(10) {
             0 => DmaTimer::Timer0,
@@ -21,6 +69,22 @@ impl Soundcnt {
         self.cnt_h.take(11)
     }
 
+    /// Left shift applied to the 8-bit FIFO B samples (50%, 100%)
+    pub fn fifo_b_volume_shift(&self) -> u16 {
+        match self.cnt_h.get(3) {
+            0 => 1,
+            _ => 2,
+        }
+    }
+
+    pub fn fifo_b_enable_right(&self) -> bool {
+        self.cnt_h.has(12)
+    }
+
+    pub fn fifo_b_enable_left(&self) -> bool {
+        self.cnt_h.has(13)
+    }
+
     pub fn timer_select_b(&self) -> DmaTimer {
         match self.cnt_h.get(14) {
             0 => DmaTimer::Timer0,
@@ -31,6 +95,10 @@ impl Soundcnt {
     pub fn reset_fifo_b(&mut self) -> bool {
         self.cnt_h.take(15)
     }
+
+    pub fn master_enable(&self) -> bool {
+        self.cnt_x.has(7)
+    }
 }
 
 impl Bus for Soundcnt {
@@ -0,0 +1,200 @@
+use crate::{
+    apu::registers::envelope::Envelope,
+    bus::Bus,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
+
+const DUTY_CYCLES: [[u8; 8]; 4] = [
+    [0, 0, 0, 0, 0, 0, 0, 1], // 12.5%
+    [1, 0, 0, 0, 0, 0, 0, 1], // 25%
+    [1, 0, 0, 0, 0, 1, 1, 1], // 50%
+    [0, 1, 1, 1, 1, 1, 1, 0], // 75%
+];
+
+/// Tone channel, channel 1 has a frequency sweep unit, channel 2 leaves `cnt_l` cleared.
+#[derive(Debug, Default)]
+pub struct Square {
+    /// Sweep (R/W)
+    pub cnt_l: u16,
+    /// Duty/Length/Envelope (R/W)
+    pub cnt_h: u16,
+    /// Frequency/Control (R/W)
+    pub cnt_x: u16,
+
+    enabled: bool,
+    divider: u32,
+    duty_step: usize,
+    length: u16,
+    envelope: Envelope,
+    sweep_enabled: bool,
+    sweep_timer: u8,
+    shadow_freq: u16,
+}
+
+impl_savestate!(Square {
+    cnt_l,
+    cnt_h,
+    cnt_x,
+    enabled,
+    divider,
+    duty_step,
+    length,
+    envelope,
+    sweep_enabled,
+    sweep_timer,
+    shadow_freq
+} if |square| square.duty_step < 8);
+
+impl Square {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn sweep_shift(&self) -> u8 {
+        self.cnt_l.get_bits_u8(0, 2)
+    }
+
+    pub fn sweep_decrease(&self) -> bool {
+        self.cnt_l.has(3)
+    }
+
+    pub fn sweep_time(&self) -> u8 {
+        self.cnt_l.get_bits_u8(4, 6)
+    }
+
+    pub fn length_load(&self) -> u16 {
+        self.cnt_h.get_bits(0, 5)
+    }
+
+    pub fn duty(&self) -> usize {
+        self.cnt_h.get_bits(6, 7).into()
+    }
+
+    pub fn envelope_cnt(&self) -> u8 {
+        self.cnt_h.get_bits_u8(8, 15)
+    }
+
+    pub fn frequency(&self) -> u16 {
+        self.cnt_x.get_bits(0, 10)
+    }
+
+    pub fn length_enable(&self) -> bool {
+        self.cnt_x.has(14)
+    }
+
+    pub fn output(&self) -> u8 {
+        if self.enabled {
+            DUTY_CYCLES[self.duty()][self.duty_step] * self.envelope.volume()
+        } else {
+            0
+        }
+    }
+
+    pub fn tick(&mut self, cycles: u32) {
+        if !self.enabled {
+            return;
+        }
+
+        let period = (2048 - self.frequency() as u32) * 16;
+
+        self.divider += cycles;
+
+        while self.divider >= period {
+            self.duty_step = (self.duty_step + 1) % 8;
+            self.divider -= period;
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length_enable() && self.length > 0 {
+            self.length -= 1;
+
+            if self.length == 0 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock(self.envelope_cnt());
+    }
+
+    pub fn clock_sweep(&mut self) {
+        self.sweep_timer = self.sweep_timer.saturating_sub(1);
+
+        if self.sweep_timer != 0 {
+            return;
+        }
+
+        self.sweep_timer = self.sweep_period();
+
+        if !self.sweep_enabled || self.sweep_time() == 0 {
+            return;
+        }
+
+        let frequency = self.sweep_frequency();
+
+        if frequency > 2047 {
+            self.enabled = false;
+        } else if self.sweep_shift() != 0 {
+            self.shadow_freq = frequency;
+            self.cnt_x.set_bits(0, 10, frequency);
+
+            if self.sweep_frequency() > 2047 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    pub fn write_cnt_h(&mut self, address: u32, value: u8) {
+        self.cnt_h.write_byte(address, value);
+
+        if address % 2 == 0 {
+            self.length = 64 - self.length_load();
+        } else if !Envelope::dac_enable(value) {
+            self.enabled = false;
+        }
+    }
+
+    pub fn write_cnt_x(&mut self, address: u32, value: u8) {
+        self.cnt_x.write_byte(address, value);
+
+        if self.cnt_x.take(15) {
+            self.trigger();
+        }
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = Envelope::dac_enable(self.envelope_cnt());
+        self.divider = 0;
+        self.envelope.reload(self.envelope_cnt());
+
+        if self.length == 0 {
+            self.length = 64;
+        }
+
+        self.shadow_freq = self.frequency();
+        self.sweep_timer = self.sweep_period();
+        self.sweep_enabled = self.sweep_time() != 0 || self.sweep_shift() != 0;
+
+        if self.sweep_shift() != 0 && self.sweep_frequency() > 2047 {
+            self.enabled = false;
+        }
+    }
+
+    fn sweep_period(&self) -> u8 {
+        match self.sweep_time() {
+            0 => 8,
+            time => time,
+        }
+    }
+
+    fn sweep_frequency(&self) -> u16 {
+        let delta = self.shadow_freq >> self.sweep_shift();
+
+        match self.sweep_decrease() {
+            true => self.shadow_freq.saturating_sub(delta),
+            false => self.shadow_freq + delta,
+        }
+    }
+}
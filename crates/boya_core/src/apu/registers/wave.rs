@@ -0,0 +1,170 @@
+use crate::{
+    bus::Bus,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
+
+pub const WAVE_RAM_SIZE: usize = 0x20; // 2 banks of 16 bytes
+
+/// Wave output channel, plays back 4-bit samples from the wave RAM.
+#[derive(Debug, Default)]
+pub struct Wave {
+    /// Stop/Wave RAM select (R/W)
+    pub cnt_l: u16,
+    /// Length/Volume (R/W)
+    pub cnt_h: u16,
+    /// Frequency/Control (R/W)
+    pub cnt_x: u16,
+    /// Wave Pattern RAM (R/W)
+    pub ram: [u8; WAVE_RAM_SIZE],
+
+    enabled: bool,
+    divider: u32,
+    position: usize,
+    length: u16,
+}
+
+impl_savestate!(Wave {
+    cnt_l,
+    cnt_h,
+    cnt_x,
+    ram,
+    enabled,
+    divider,
+    position,
+    length
+} if |wave| wave.position < 32);
+
+impl Wave {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn two_banks(&self) -> bool {
+        self.cnt_l.has(5)
+    }
+
+    pub fn bank(&self) -> usize {
+        self.cnt_l.get(6).into()
+    }
+
+    pub fn dac_enable(&self) -> bool {
+        self.cnt_l.has(7)
+    }
+
+    pub fn length_load(&self) -> u16 {
+        self.cnt_h.get_bits(0, 7)
+    }
+
+    pub fn frequency(&self) -> u16 {
+        self.cnt_x.get_bits(0, 10)
+    }
+
+    pub fn length_enable(&self) -> bool {
+        self.cnt_x.has(14)
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+
+        let byte = self.ram[self.bank() * 16 + self.position / 2];
+
+        let sample = match self.position % 2 {
+            0 => byte.get_bits(4, 7),
+            _ => byte.get_bits(0, 3),
+        };
+
+        match (self.cnt_h.has(15), self.cnt_h.get_bits(13, 14)) {
+            (true, _) => sample * 3 / 4,
+            (false, 0) => 0,
+            (false, 1) => sample,
+            (false, 2) => sample >> 1,
+            (false, _) => sample >> 2,
+        }
+    }
+
+    pub fn tick(&mut self, cycles: u32) {
+        if !self.enabled {
+            return;
+        }
+
+        let period = (2048 - self.frequency() as u32) * 8;
+
+        self.divider += cycles;
+
+        while self.divider >= period {
+            self.advance();
+            self.divider -= period;
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length_enable() && self.length > 0 {
+            self.length -= 1;
+
+            if self.length == 0 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    pub fn write_cnt_l(&mut self, address: u32, value: u8) {
+        self.cnt_l.write_byte(address, value);
+
+        if !self.dac_enable() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn write_cnt_h(&mut self, address: u32, value: u8) {
+        self.cnt_h.write_byte(address, value);
+
+        if address % 2 == 0 {
+            self.length = 256 - self.length_load();
+        }
+    }
+
+    pub fn write_cnt_x(&mut self, address: u32, value: u8) {
+        self.cnt_x.write_byte(address, value);
+
+        if self.cnt_x.take(15) {
+            self.trigger();
+        }
+    }
+
+    /// The CPU can only access the bank that is not currently being played.
+    pub fn read_ram(&self, address: u32) -> u8 {
+        self.ram[self.ram_offset(address)]
+    }
+
+    pub fn write_ram(&mut self, address: u32, value: u8) {
+        self.ram[self.ram_offset(address)] = value;
+    }
+
+    fn ram_offset(&self, address: u32) -> usize {
+        (self.bank() ^ 1) * 16 + (address as usize & 0xF)
+    }
+
+    fn advance(&mut self) {
+        self.position += 1;
+
+        if self.position == 32 {
+            self.position = 0;
+
+            if self.two_banks() {
+                self.cnt_l ^= 1 << 6;
+            }
+        }
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enable();
+        self.divider = 0;
+        self.position = 0;
+
+        if self.length == 0 {
+            self.length = 256;
+        }
+    }
+}
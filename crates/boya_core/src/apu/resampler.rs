@@ -0,0 +1,106 @@
+use crate::utils::collections::FifoBuffer;
+
+/// The APU output is sampled at the hardware default rate (9bit / 32.768kHz)
+pub const NATIVE_SAMPLE_RATE: u32 = 32768;
+pub const DEFAULT_SAMPLE_RATE: u32 = 48000;
+pub const SAMPLE_BUFFER_LEN: usize = 8192; // stereo frames
+
+pub type StereoSample = (i16, i16);
+
+/// Linear resampler from the native APU rate to the frontend output rate.
+#[derive(Debug)]
+pub struct Resampler {
+    buffer: Box<FifoBuffer<StereoSample, SAMPLE_BUFFER_LEN>>,
+    output_rate: u32,
+    position: f32,
+    previous: StereoSample,
+}
+
+impl Default for Resampler {
+    fn default() -> Self {
+        Self {
+            buffer: Box::default(),
+            output_rate: DEFAULT_SAMPLE_RATE,
+            position: 0.0,
+            previous: (0, 0),
+        }
+    }
+}
+
+impl Resampler {
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    pub fn set_output_rate(&mut self, rate: u32) {
+        self.output_rate = rate.max(1);
+        self.position = 0.0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.position = 0.0;
+        self.previous = (0, 0);
+    }
+
+    pub fn push(&mut self, sample: StereoSample) {
+        let step = NATIVE_SAMPLE_RATE as f32 / self.output_rate as f32;
+
+        while self.position < 1.0 {
+            let left = lerp(self.previous.0, sample.0, self.position);
+            let right = lerp(self.previous.1, sample.1, self.position);
+
+            self.buffer.push((left, right));
+            self.position += step;
+        }
+
+        self.position -= 1.0;
+        self.previous = sample;
+    }
+
+    pub fn pop(&mut self) -> Option<StereoSample> {
+        self.buffer.pop()
+    }
+}
+
+fn lerp(a: i16, b: i16, t: f32) -> i16 {
+    (a as f32 + (b as f32 - a as f32) * t) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::apu::resampler::{NATIVE_SAMPLE_RATE, Resampler};
+
+    #[test]
+    fn test_resampling_rate() {
+        let mut resampler = Resampler::default();
+
+        resampler.set_output_rate(NATIVE_SAMPLE_RATE * 2);
+
+        for _ in 0..100 {
+            resampler.push((1000, -1000));
+        }
+
+        assert_eq!(resampler.len(), 200);
+        assert_eq!(resampler.pop(), Some((0, 0)));
+        assert_eq!(resampler.pop(), Some((500, -500)));
+        assert_eq!(resampler.pop(), Some((1000, -1000)));
+
+        resampler.clear();
+        resampler.set_output_rate(NATIVE_SAMPLE_RATE / 2);
+
+        for _ in 0..100 {
+            resampler.push((1000, -1000));
+        }
+
+        assert_eq!(resampler.len(), 50);
+    }
+}
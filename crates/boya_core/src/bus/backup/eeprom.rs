@@ -0,0 +1,222 @@
+use crate::utils::savestate::{impl_savestate, impl_savestate_enum};
+
+pub const EEPROM_SIZE: usize = 0x2000; // 8kb
+pub const EEPROM_SMALL_SIZE: usize = 0x200; // 512b
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum EepromState {
+    #[default]
+    Command,
+    ReadAddress,
+    ReadStop,
+    WriteAddress,
+    WriteData,
+    WriteStop,
+}
+
+impl_savestate_enum!(EepromState {
+    Command,
+    ReadAddress,
+    ReadStop,
+    WriteAddress,
+    WriteData,
+    WriteStop
+});
+
+/// Serial EEPROM backup, accessed one bit per halfword through DMA 3.
+#[derive(Debug)]
+pub struct Eeprom {
+    pub data: Vec<u8>,
+
+    address_bits: Option<u32>,
+    state: EepromState,
+    buffer: u64,
+    count: u32,
+    address: usize,
+    read_bits: u32,
+}
+
+impl Default for Eeprom {
+    fn default() -> Self {
+        Self {
+            data: vec![0xFF; EEPROM_SIZE],
+            address_bits: None,
+            state: EepromState::Command,
+            buffer: 0,
+            count: 0,
+            address: 0,
+            read_bits: 0,
+        }
+    }
+}
+
+impl Eeprom {
+    pub fn with_size(size: usize) -> Self {
+        Self {
+            address_bits: Some(if size <= EEPROM_SMALL_SIZE { 6 } else { 14 }),
+            ..Self::default()
+        }
+    }
+
+    /// 6-bit (512b) or 14-bit (8kb) bus width, unknown until the first DMA transfer.
+    pub fn address_bits(&self) -> Option<u32> {
+        self.address_bits
+    }
+
+    pub fn size(&self) -> usize {
+        match self.address_bits {
+            Some(6) => EEPROM_SMALL_SIZE,
+            _ => EEPROM_SIZE,
+        }
+    }
+
+    /// Infers the bus width from the length of a read request or write DMA.
+    pub fn detect_width(&mut self, transfer_len: u32) {
+        if self.address_bits.is_some() {
+            return;
+        }
+
+        self.address_bits = match transfer_len {
+            9 | 73 => Some(6),
+            17 | 81 => Some(14),
+            _ => None,
+        };
+    }
+
+    /// Next serial bit, without shifting it out.
+    pub fn peek(&self) -> u16 {
+        match self.read_bits {
+            0 => 1, // ready
+            1..=64 => (self.read_block() >> (self.read_bits - 1)) as u16 & 1,
+            _ => 0,
+        }
+    }
+
+    pub fn read(&mut self) -> u16 {
+        let bit = self.peek();
+
+        self.read_bits = self.read_bits.saturating_sub(1);
+        bit
+    }
+
+    /// Returns whether the backup contents were modified.
+    pub fn write(&mut self, value: u8) -> bool {
+        self.buffer = (self.buffer << 1) | (value & 1) as u64;
+        self.count += 1;
+
+        let address_bits = self.address_bits.unwrap_or(14);
+
+        let next = match self.state {
+            EepromState::Command if self.count == 2 => match self.buffer {
+                0b11 => EepromState::ReadAddress,
+                0b10 => EepromState::WriteAddress,
+                _ => EepromState::Command,
+            },
+            EepromState::ReadAddress if self.count == address_bits => {
+                self.address = self.block_offset();
+                EepromState::ReadStop
+            }
+            EepromState::WriteAddress if self.count == address_bits => {
+                self.address = self.block_offset();
+                self.read_bits = 0;
+                EepromState::WriteData
+            }
+            EepromState::WriteData if self.count == 64 => {
+                self.data[self.address..self.address + 8]
+                    .copy_from_slice(&self.buffer.to_be_bytes());
+                EepromState::WriteStop
+            }
+            EepromState::ReadStop => {
+                self.read_bits = 68; // 4 dummy bits followed by 64 data bits
+                EepromState::Command
+            }
+            EepromState::WriteStop => EepromState::Command,
+            _ => return false,
+        };
+
+        self.state = next;
+        self.buffer = 0;
+        self.count = 0;
+        self.state == EepromState::WriteStop
+    }
+
+    /// Imports a save, the bus width is inferred from its size unless it is already known.
+    pub fn load(&mut self, data: &[u8]) {
+        let len = data.len().min(EEPROM_SIZE);
+
+        if self.address_bits.is_none() && len > 0 {
+            self.address_bits = Some(if len <= EEPROM_SMALL_SIZE { 6 } else { 14 });
+        }
+
+        self.data.fill(0xFF);
+        self.data[..len].copy_from_slice(&data[..len]);
+    }
+
+    pub fn reset(&mut self) {
+        self.state = EepromState::Command;
+        self.buffer = 0;
+        self.count = 0;
+        self.read_bits = 0;
+    }
+
+    fn block_offset(&self) -> usize {
+        (self.buffer as usize * 8) % self.size()
+    }
+
+    fn read_block(&self) -> u64 {
+        u64::from_be_bytes(
+            self.data[self.address..self.address + 8]
+                .try_into()
+                .unwrap(),
+        )
+    }
+}
+
+impl_savestate!(Eeprom {
+    data,
+    address_bits,
+    state,
+    buffer,
+    count,
+    address,
+    read_bits
+} if |eeprom| eeprom.data.len() == EEPROM_SIZE && eeprom.address + 8 <= EEPROM_SIZE);
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::backup::eeprom::Eeprom;
+
+    fn send(eeprom: &mut Eeprom, value: u64, bits: u32) {
+        for bit in (0..bits).rev() {
+            eeprom.write((value >> bit) as u8 & 1);
+        }
+    }
+
+    #[test]
+    fn test_eeprom_read_write() {
+        let mut eeprom = Eeprom::default();
+
+        eeprom.detect_width(81);
+        assert_eq!(eeprom.address_bits(), Some(14));
+
+        send(&mut eeprom, 0b10, 2);
+        send(&mut eeprom, 0x21, 14);
+        send(&mut eeprom, 0x0123_4567_89AB_CDEF, 64);
+        send(&mut eeprom, 0, 1);
+
+        assert_eq!(
+            eeprom.data[0x108..0x110],
+            0x0123_4567_89AB_CDEFu64.to_be_bytes()
+        );
+        assert_eq!(eeprom.read(), 1, "ready");
+
+        send(&mut eeprom, 0b11, 2);
+        send(&mut eeprom, 0x21, 14);
+        send(&mut eeprom, 0, 1);
+
+        let bits = (0..68).map(|_| eeprom.read() as u64);
+        let value = bits.skip(4).fold(0, |acc, bit| (acc << 1) | bit);
+
+        assert_eq!(value, 0x0123_4567_89AB_CDEF);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::utils::savestate::{impl_savestate, impl_savestate_enum};
+
+pub const FLASH_BANK_SIZE: usize = 0x10000; // 64kb
+pub const FLASH_SECTOR_SIZE: usize = 0x1000; // 4kb
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum FlashState {
+    #[default]
+    Ready,
+    Command1,
+    Command2,
+    Program,
+    Bank,
+}
+
+impl_savestate_enum!(FlashState {
+    Ready,
+    Command1,
+    Command2,
+    Program,
+    Bank
+});
+
+/// Flash backup, 64kb (Panasonic) or 128kb with two switchable banks (Sanyo).
+#[derive(Debug)]
+pub struct Flash {
+    pub data: Vec<u8>,
+
+    state: FlashState,
+    bank: usize,
+    id_mode: bool,
+    erase_mode: bool,
+}
+
+impl Flash {
+    pub fn new(banks: usize) -> Self {
+        Self {
+            data: vec![0xFF; banks * FLASH_BANK_SIZE],
+            state: FlashState::Ready,
+            bank: 0,
+            id_mode: false,
+            erase_mode: false,
+        }
+    }
+
+    /// Manufacturer and device code returned in chip identification mode
+    pub fn chip_id(&self) -> [u8; 2] {
+        match self.banks() {
+            1 => [0x32, 0x1B],
+            _ => [0x62, 0x13],
+        }
+    }
+
+    pub fn banks(&self) -> usize {
+        self.data.len() / FLASH_BANK_SIZE
+    }
+
+    pub fn read(&self, address: u32) -> u8 {
+        let offset = address as usize % FLASH_BANK_SIZE;
+
+        if self.id_mode && offset < 2 {
+            return self.chip_id()[offset];
+        }
+
+        self.data[self.bank * FLASH_BANK_SIZE + offset]
+    }
+
+    /// Returns whether the backup contents were modified.
+    pub fn write(&mut self, address: u32, value: u8) -> bool {
+        let offset = address as usize % FLASH_BANK_SIZE;
+        let mut modified = false;
+
+        self.state = match (self.state, offset, value) {
+            (FlashState::Program, _, _) => {
+                self.data[self.bank * FLASH_BANK_SIZE + offset] = value;
+                modified = true;
+                FlashState::Ready
+            }
+            (FlashState::Bank, 0x0000, _) => {
+                self.bank = value as usize % self.banks();
+                FlashState::Ready
+            }
+            (FlashState::Ready, 0x5555, 0xAA) => FlashState::Command1,
+            (FlashState::Command1, 0x2AAA, 0x55) => FlashState::Command2,
+            (FlashState::Command2, _, _) => {
+                modified = self.erase_mode && matches!(value, 0x10 | 0x30);
+                self.command(offset, value)
+            }
+            _ => FlashState::Ready,
+        };
+
+        modified
+    }
+
+    /// Imports a save, keeping the detected chip unless the save needs a larger one.
+    pub fn load(&mut self, data: &[u8]) {
+        let banks = data.len().div_ceil(FLASH_BANK_SIZE).clamp(self.banks(), 2);
+        let len = data.len().min(banks * FLASH_BANK_SIZE);
+
+        *self = Self::new(banks);
+        self.data[..len].copy_from_slice(&data[..len]);
+    }
+
+    pub fn reset(&mut self) {
+        self.state = FlashState::Ready;
+        self.bank = 0;
+        self.id_mode = false;
+        self.erase_mode = false;
+    }
+
+    fn command(&mut self, offset: usize, value: u8) -> FlashState {
+        let erase_mode = std::mem::replace(&mut self.erase_mode, false);
+
+        match (offset, value) {
+            (0x5555, 0x90) => self.id_mode = true,
+            (0x5555, 0xF0) => self.id_mode = false,
+            (0x5555, 0x80) => self.erase_mode = true,
+            (0x5555, 0x10) if erase_mode => self.data.fill(0xFF),
+            (_, 0x30) if erase_mode => {
+                let start = self.bank * FLASH_BANK_SIZE + (offset & !(FLASH_SECTOR_SIZE - 1));
+                self.data[start..start + FLASH_SECTOR_SIZE].fill(0xFF);
+            }
+            (0x5555, 0xA0) => return FlashState::Program,
+            (0x5555, 0xB0) if self.banks() > 1 => return FlashState::Bank,
+            _ => {}
+        }
+
+        FlashState::Ready
+    }
+}
+
+impl_savestate!(Flash {
+    data,
+    state,
+    bank,
+    id_mode,
+    erase_mode
+} if |flash| matches!(flash.banks(), 1 | 2)
+    && flash.data.len() == flash.banks() * FLASH_BANK_SIZE
+    && flash.bank < flash.banks());
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::backup::flash::Flash;
+
+    fn command(flash: &mut Flash, value: u8) {
+        flash.write(0x0E00_5555, 0xAA);
+        flash.write(0x0E00_2AAA, 0x55);
+        flash.write(0x0E00_5555, value);
+    }
+
+    #[test]
+    fn test_flash_commands() {
+        let mut flash = Flash::new(2);
+
+        command(&mut flash, 0x90);
+        assert_eq!(
+            [flash.read(0x0E00_0000), flash.read(0x0E00_0001)],
+            [0x62, 0x13]
+        );
+
+        command(&mut flash, 0xF0);
+        assert_eq!(flash.read(0x0E00_0000), 0xFF);
+
+        command(&mut flash, 0xA0);
+        flash.write(0x0E00_1234, 0x42);
+        assert_eq!(flash.read(0x0E00_1234), 0x42);
+
+        command(&mut flash, 0xB0);
+        flash.write(0x0E00_0000, 1);
+        assert_eq!(flash.read(0x0E00_1234), 0xFF, "bank 1");
+
+        command(&mut flash, 0xA0);
+        flash.write(0x0E00_1234, 0x24);
+
+        command(&mut flash, 0xB0);
+        flash.write(0x0E00_0000, 0);
+        assert_eq!(flash.read(0x0E00_1234), 0x42, "bank 0");
+
+        command(&mut flash, 0x80);
+        flash.write(0x0E00_5555, 0xAA);
+        flash.write(0x0E00_2AAA, 0x55);
+        flash.write(0x0E00_1000, 0x30);
+        assert_eq!(flash.read(0x0E00_1234), 0xFF, "sector erased");
+        assert_eq!(flash.data[0x11234], 0x24);
+
+        command(&mut flash, 0x80);
+        command(&mut flash, 0x10);
+        assert!(flash.data.iter().all(|&b| b == 0xFF), "chip erased");
+    }
+}
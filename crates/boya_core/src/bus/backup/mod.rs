@@ -0,0 +1,186 @@
+pub mod eeprom;
+pub mod flash;
+
+use crate::{
+    bus::{
+        SRAM_SIZE,
+        backup::{eeprom::Eeprom, flash::Flash},
+    },
+    rom::SaveType,
+    utils::{
+        Reset,
+        savestate::{Savestate, StateError, StateReader, StateWriter},
+    },
+};
+
+/// Cartridge backup memory mapped at 0x0E00_0000.
+#[derive(Debug)]
+pub enum Backup {
+    Sram(Box<[u8; SRAM_SIZE]>),
+    Flash(Flash),
+    Eeprom(Eeprom),
+}
+
+impl Default for Backup {
+    fn default() -> Self {
+        Self::Sram(Box::new([0; SRAM_SIZE]))
+    }
+}
+
+impl Backup {
+    /// The size only matters for EEPROM, whose bus width is otherwise found out on the first
+    /// transfer.
+    pub fn new(save_type: SaveType, size: Option<usize>) -> Self {
+        match (save_type, size) {
+            (SaveType::Sram, _) => Self::default(),
+            (SaveType::Flash64K, _) => Self::Flash(Flash::new(1)),
+            (SaveType::Flash128K, _) => Self::Flash(Flash::new(2)),
+            (SaveType::Eeprom, Some(size)) => Self::Eeprom(Eeprom::with_size(size)),
+            (SaveType::Eeprom, None) => Self::Eeprom(Eeprom::default()),
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        match self {
+            Self::Sram(sram) => sram.as_slice(),
+            Self::Flash(flash) => &flash.data,
+            Self::Eeprom(eeprom) => &eeprom.data[..eeprom.size()],
+        }
+    }
+
+    pub fn read(&self, address: u32) -> u8 {
+        match self {
+            Self::Sram(sram) => sram[address as usize % SRAM_SIZE],
+            Self::Flash(flash) => flash.read(address),
+            Self::Eeprom(_) => 0xFF,
+        }
+    }
+
+    /// Returns whether the backup contents were modified.
+    pub fn write(&mut self, address: u32, value: u8) -> bool {
+        match self {
+            Self::Sram(sram) => {
+                sram[address as usize % SRAM_SIZE] = value;
+                true
+            }
+            Self::Flash(flash) => flash.write(address, value),
+            Self::Eeprom(_) => false,
+        }
+    }
+
+    /// Imports a `.sav` file, which is a raw dump of the backup memory.
+    pub fn load(&mut self, data: &[u8]) {
+        match self {
+            // 64kb dumps from emulators that don't mirror the chip only use their first half
+            Self::Sram(sram) => {
+                let len = data.len().min(SRAM_SIZE);
+
+                sram.fill(0);
+                sram[..len].copy_from_slice(&data[..len]);
+            }
+            Self::Flash(flash) => flash.load(data),
+            Self::Eeprom(eeprom) => eeprom.load(data),
+        }
+    }
+}
+
+impl Reset for Backup {
+    fn reset(&mut self) {
+        match self {
+            Self::Sram(_) => {}
+            Self::Flash(flash) => flash.reset(),
+            Self::Eeprom(eeprom) => eeprom.reset(),
+        }
+    }
+}
+
+/// The backup type is stored first, so a state can be restored before the ROM is detected.
+impl Savestate for Backup {
+    fn save_state(&self, writer: &mut StateWriter) {
+        match self {
+            Self::Sram(sram) => {
+                0u8.save_state(writer);
+                sram.save_state(writer);
+            }
+            Self::Flash(flash) => {
+                1u8.save_state(writer);
+                flash.banks().save_state(writer);
+                flash.save_state(writer);
+            }
+            Self::Eeprom(eeprom) => {
+                2u8.save_state(writer);
+                eeprom.save_state(writer);
+            }
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        let mut kind = 0u8;
+
+        kind.load_state(reader)?;
+
+        match kind {
+            0 => *self = Self::Sram(Box::new([0; SRAM_SIZE])),
+            1 => {
+                let mut banks = 0usize;
+
+                banks.load_state(reader)?;
+                *self = Self::Flash(Flash::new(banks.clamp(1, 2)));
+            }
+            2 => *self = Self::Eeprom(Eeprom::default()),
+            _ => return Err(StateError::InvalidValue("Backup")),
+        }
+
+        match self {
+            Self::Sram(sram) => sram.load_state(reader),
+            Self::Flash(flash) => flash.load_state(reader),
+            Self::Eeprom(eeprom) => eeprom.load_state(reader),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bus::{
+            SRAM_SIZE,
+            backup::{Backup, eeprom::EEPROM_SIZE, flash::FLASH_BANK_SIZE},
+        },
+        rom::SaveType,
+    };
+
+    #[test]
+    fn test_backup_load() {
+        let mut backup = Backup::new(SaveType::Flash64K, None);
+        let save = vec![0x42; FLASH_BANK_SIZE * 2];
+
+        backup.load(&save);
+        assert_eq!(backup.data(), save, "upgraded to 128kb flash");
+
+        let mut backup = Backup::new(SaveType::Flash128K, None);
+
+        backup.load(&save[..FLASH_BANK_SIZE]);
+        assert_eq!(backup.data().len(), FLASH_BANK_SIZE * 2, "kept 128kb flash");
+
+        let mut backup = Backup::new(SaveType::Eeprom, None);
+
+        backup.load(&[0x42; 0x200]);
+        assert_eq!(backup.data(), [0x42; 0x200], "512b EEPROM");
+
+        let mut backup = Backup::new(SaveType::Eeprom, Some(EEPROM_SIZE));
+
+        backup.load(&[0x42; 0x200]);
+        assert_eq!(backup.data().len(), EEPROM_SIZE, "detected 8kb EEPROM");
+
+        let mut backup = Backup::new(SaveType::Sram, None);
+
+        assert!(backup.write(0x0E00_8001, 0x42));
+        assert_eq!(backup.read(0x0E00_0001), 0x42, "mirrored");
+
+        let mut save = vec![0; SRAM_SIZE * 2];
+
+        save[1] = 0x42;
+        backup.load(&save);
+        assert_eq!(backup.read(0x0E00_8001), 0x42, "64kb import");
+    }
+}
@@ -0,0 +1,304 @@
+pub mod rtc;
+pub mod sensors;
+
+use crate::{
+    bus::gpio::{
+        rtc::{HostClock, Rtc, RtcClock},
+        sensors::{Gyro, SensorInput, SolarSensor, TiltSensor},
+    },
+    rom::GpioDevices,
+    utils::{Reset, bitflags::Bitflag, savestate::impl_savestate},
+};
+
+/// 4-bit I/O port in the ROM header area at 0x0800_00C4, wired to cartridge peripherals.
+#[derive(Debug)]
+pub struct Gpio {
+    /// 0x0C4: I/O Port Data (R/W)
+    pub data: u8,
+    /// 0x0C6: I/O Port Direction, set bits are outputs (R/W)
+    pub direction: u8,
+    /// 0x0C8: I/O Port Control, the registers read as ROM unless set (R/W)
+    pub readable: bool,
+    pub input: SensorInput,
+
+    rtc: Option<Rtc>,
+    solar: Option<SolarSensor>,
+    gyro: Option<Gyro>,
+    /// Motor state, driven by pin 3.
+    rumble: Option<bool>,
+    /// Not wired to the port, but mapped in the backup area.
+    tilt: Option<TiltSensor>,
+    clock: Box<dyn RtcClock>,
+}
+
+impl Default for Gpio {
+    fn default() -> Self {
+        Self {
+            data: 0,
+            direction: 0,
+            readable: false,
+            input: SensorInput::default(),
+            rtc: None,
+            solar: None,
+            gyro: None,
+            rumble: None,
+            tilt: None,
+            clock: Box::new(HostClock),
+        }
+    }
+}
+
+impl_savestate!(Gpio {
+    data,
+    direction,
+    readable,
+    rtc,
+    solar,
+    gyro,
+    rumble,
+    tilt
+});
+
+impl Gpio {
+    /// Wires the peripherals of the cartridge, keeping the clock source and the host input.
+    pub fn attach(&mut self, devices: GpioDevices) {
+        self.rtc = devices.rtc.then(Rtc::default);
+        self.solar = devices.solar.then(SolarSensor::default);
+        self.gyro = devices.gyro.then(Gyro::default);
+        self.rumble = devices.rumble.then_some(false);
+        self.tilt = devices.tilt.then(TiltSensor::default);
+        self.reset();
+    }
+
+    pub fn set_clock(&mut self, clock: Box<dyn RtcClock>) {
+        self.clock = clock;
+    }
+
+    /// Takes over the clock source and the host input of the port this one replaces.
+    pub fn inherit(&mut self, previous: Gpio) {
+        self.input = previous.input;
+        self.clock = previous.clock;
+    }
+
+    pub fn has_devices(&self) -> bool {
+        self.rtc.is_some() || self.solar.is_some() || self.gyro.is_some() || self.rumble.is_some()
+    }
+
+    pub fn has_tilt(&self) -> bool {
+        self.tilt.is_some()
+    }
+
+    /// Whether the rumble motor is spinning.
+    pub fn rumble(&self) -> bool {
+        self.rumble.unwrap_or_default()
+    }
+
+    /// Returns `None` when the port isn't readable, the ROM is visible instead.
+    pub fn read(&self, address: u32) -> Option<u8> {
+        if !self.readable || !self.has_devices() {
+            return None;
+        }
+
+        let value = match address & 0xFF {
+            0xC4 => self.read_data(),
+            0xC6 => self.direction,
+            0xC8 => self.readable as u8,
+            _ => 0,
+        };
+
+        Some(value)
+    }
+
+    pub fn write(&mut self, address: u32, value: u8) {
+        match address & 0xFF {
+            0xC4 => {
+                self.data = value & 0xF;
+                self.write_pins(self.data & self.direction);
+            }
+            0xC6 => self.direction = value & 0xF,
+            0xC8 => self.readable = value & 1 != 0,
+            _ => {}
+        }
+    }
+
+    pub fn read_tilt(&self, address: u32) -> u8 {
+        self.tilt.as_ref().map_or(0xFF, |tilt| tilt.read(address))
+    }
+
+    pub fn write_tilt(&mut self, address: u32, value: u8) {
+        if let Some(tilt) = &mut self.tilt {
+            tilt.write(address, value, &self.input);
+        }
+    }
+
+    fn write_pins(&mut self, pins: u8) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.write_pins(pins, self.clock.as_ref());
+        }
+
+        if let Some(solar) = &mut self.solar {
+            solar.write_pins(pins, self.input.light);
+        }
+
+        if let Some(gyro) = &mut self.gyro {
+            gyro.write_pins(pins, self.input.gyro);
+        }
+
+        if let Some(rumble) = &mut self.rumble {
+            *rumble = pins.has(3);
+        }
+    }
+
+    /// Output pins read back what was written, input pins are driven by the devices.
+    fn read_data(&self) -> u8 {
+        let input = self.rtc.as_ref().map_or(0, Rtc::output)
+            | self.solar.as_ref().map_or(0, SolarSensor::output)
+            | self.gyro.as_ref().map_or(0, Gyro::output);
+
+        (self.data & self.direction) | (input & !self.direction & 0xF)
+    }
+}
+
+impl Reset for Gpio {
+    fn reset(&mut self) {
+        self.data = 0;
+        self.direction = 0;
+        self.readable = false;
+
+        self.rtc = self.rtc.take().map(|_| Rtc::default());
+        self.solar = self.solar.take().map(|_| SolarSensor::default());
+        self.gyro = self.gyro.take().map(|_| Gyro::default());
+        self.rumble = self.rumble.map(|_| false);
+        self.tilt = self.tilt.take().map(|_| TiltSensor::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bus::gpio::{Gpio, rtc::FixedClock},
+        rom::GpioDevices,
+        utils::bitflags::Bitflag,
+    };
+
+    const GPIO_DATA: u32 = 0x0800_00C4;
+    const GPIO_DIRECTION: u32 = 0x0800_00C6;
+    const GPIO_CONTROL: u32 = 0x0800_00C8;
+
+    fn start(gpio: &mut Gpio) {
+        gpio.write(GPIO_DIRECTION, 0b111);
+        gpio.write(GPIO_DATA, 0b001);
+        gpio.write(GPIO_DATA, 0b101);
+    }
+
+    fn send_byte(gpio: &mut Gpio, byte: u8) {
+        for bit in 0..8 {
+            let sio = (byte >> bit & 1) << 1;
+
+            gpio.write(GPIO_DATA, 0b100 | sio);
+            gpio.write(GPIO_DATA, 0b101 | sio);
+        }
+    }
+
+    fn receive_byte(gpio: &mut Gpio) -> u8 {
+        gpio.write(GPIO_DIRECTION, 0b101);
+
+        (0..8).fold(0, |byte, bit| {
+            gpio.write(GPIO_DATA, 0b100);
+            gpio.write(GPIO_DATA, 0b101);
+
+            let sio = gpio.read(GPIO_DATA).unwrap() >> 1 & 1;
+            byte | sio << bit
+        })
+    }
+
+    #[test]
+    fn test_rtc_datetime() {
+        let mut gpio = Gpio::default();
+
+        assert_eq!(gpio.read(GPIO_DATA), None, "no device");
+
+        gpio.set_clock(Box::new(FixedClock(1_101_044_730))); // 2004-11-21 13:45:30, sunday
+        gpio.attach(GpioDevices {
+            rtc: true,
+            ..Default::default()
+        });
+        assert_eq!(gpio.read(GPIO_DATA), None, "write-only");
+
+        gpio.write(GPIO_CONTROL, 1);
+        start(&mut gpio);
+        send_byte(&mut gpio, 0xA6); // read date/time
+
+        let datetime = [0; 7].map(|_| receive_byte(&mut gpio));
+        assert_eq!(datetime, [0x04, 0x11, 0x21, 0x00, 0x13, 0x45, 0x30]);
+
+        gpio.write(GPIO_DATA, 0b001); // CS low ends the transfer
+        start(&mut gpio);
+        send_byte(&mut gpio, 0x46); // write control
+        send_byte(&mut gpio, 0x00); // 12-hour mode
+
+        gpio.write(GPIO_DATA, 0b001);
+        start(&mut gpio);
+        send_byte(&mut gpio, 0xE6); // read time
+
+        let time = [0; 3].map(|_| receive_byte(&mut gpio));
+        assert_eq!(time, [0x81, 0x45, 0x30], "1 PM");
+    }
+
+    #[test]
+    fn test_sensors() {
+        let mut gpio = Gpio::default();
+
+        gpio.attach(GpioDevices {
+            solar: true,
+            gyro: true,
+            rumble: true,
+            tilt: true,
+            ..Default::default()
+        });
+        gpio.write(GPIO_CONTROL, 1);
+
+        // solar: count clock pulses until the flag is raised
+        gpio.input.light = 0xF0;
+        gpio.write(GPIO_DIRECTION, 0b0111);
+        gpio.write(GPIO_DATA, 0b0010);
+        gpio.write(GPIO_DATA, 0b0000);
+
+        let pulses = (0..0x100)
+            .take_while(|_| {
+                gpio.write(GPIO_DATA, 0b0001);
+                gpio.write(GPIO_DATA, 0b0000);
+                !gpio.read(GPIO_DATA).unwrap().has(3)
+            })
+            .count();
+
+        assert_eq!(pulses, 0xF - 1);
+
+        // gyro: sample, then shift 16 bits out on the falling edges of pin 1
+        gpio.input.gyro = 0x2000;
+        gpio.write(GPIO_DIRECTION, 0b1011);
+        gpio.write(GPIO_DATA, 0b1001);
+
+        let sample = (0..16).fold(0u16, |sample, _| {
+            gpio.write(GPIO_DATA, 0b1010);
+            gpio.write(GPIO_DATA, 0b1000);
+            sample << 1 | (gpio.read(GPIO_DATA).unwrap() >> 2 & 1) as u16
+        });
+
+        assert_eq!(sample, 0x6C0 + 0x100);
+        assert!(gpio.rumble(), "pin 3 drives the motor");
+
+        // tilt: sampled by writing 0x55 then 0xAA
+        gpio.input.tilt_x = -0x2000;
+        gpio.input.tilt_y = 0x2000;
+        gpio.write_tilt(0x0E00_8000, 0x55);
+        gpio.write_tilt(0x0E00_8100, 0xAA);
+
+        let x =
+            gpio.read_tilt(0x0E00_8200) as u16 | (gpio.read_tilt(0x0E00_8300) as u16 & 0xF) << 8;
+        let y = gpio.read_tilt(0x0E00_8400) as u16 | (gpio.read_tilt(0x0E00_8500) as u16) << 8;
+
+        assert_eq!((x, y), (0x3A0 - 0x100, 0x3A0 + 0x100));
+        assert!(gpio.read_tilt(0x0E00_8300).has(7), "sample ready");
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    fmt::Debug,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::utils::{bitflags::Bitflag, savestate::impl_savestate};
+
+/// Bytes following each command, indexed by command.
+const COMMAND_BYTES: [u8; 8] = [0, 0, 7, 0, 1, 0, 3, 0];
+
+const CMD_RESET: u8 = 0;
+const CMD_DATETIME: u8 = 2;
+const CMD_CONTROL: u8 = 4;
+const CMD_TIME: u8 = 6;
+
+/// 24-hour mode, set at power on.
+const CONTROL_HOUR24: u8 = 1 << 6;
+
+/// Source of the current time, in seconds since the Unix epoch.
+pub trait RtcClock: Debug + Send {
+    fn now(&self) -> u64;
+}
+
+/// UTC time of the host, not available on `wasm32-unknown-unknown` where a clock has to be injected.
+#[derive(Debug, Default)]
+pub struct HostClock;
+
+impl RtcClock for HostClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs())
+    }
+}
+
+/// Frozen clock, for deterministic runs.
+#[derive(Debug, Default)]
+pub struct FixedClock(pub u64);
+
+impl RtcClock for FixedClock {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Seiko S-3511 real-time clock, wired to SCK (pin 0), SIO (pin 1) and CS (pin 2).
+#[derive(Debug)]
+pub struct Rtc {
+    /// Start sequence progress: SCK high, then CS high, 2 while transferring.
+    step: u8,
+    /// Byte being shifted in, LSB first.
+    bits: u8,
+    bits_read: u8,
+    bytes_remaining: u8,
+    /// Command byte: 0110 magic in bits 0-3, command in bits 4-6, bit 7 set for reads.
+    command: Option<u8>,
+    control: u8,
+    /// BCD year, month, day, weekday, hour, minute and second, latched by the date/time commands.
+    time: [u8; 7],
+    sio: bool,
+}
+
+impl Default for Rtc {
+    fn default() -> Self {
+        Self {
+            step: 0,
+            bits: 0,
+            bits_read: 0,
+            bytes_remaining: 0,
+            command: None,
+            control: CONTROL_HOUR24,
+            time: [0; 7],
+            sio: false,
+        }
+    }
+}
+
+impl_savestate!(Rtc {
+    step,
+    bits,
+    bits_read,
+    bytes_remaining,
+    command,
+    control,
+    time,
+    sio
+});
+
+impl Rtc {
+    /// Pins driven by the chip, only SIO while data is read.
+    pub fn output(&self) -> u8 {
+        (self.sio as u8) << 1
+    }
+
+    pub fn write_pins(&mut self, pins: u8, clock: &dyn RtcClock) {
+        let (sck, sio, cs) = (pins.has(0), pins.has(1), pins.has(2));
+
+        match self.step {
+            0 if sck && !cs => self.step = 1,
+            1 if sck && cs => self.step = 2,
+            1 if !sck || cs => self.step = 0,
+            2 if !sck => self.bits.update(self.bits_read, sio),
+            2 if cs => self.clock_bit(clock),
+            2 => {
+                self.bits_read = 0;
+                self.bytes_remaining = 0;
+                self.command = None;
+                self.step = sck as u8;
+                self.sio = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn reading(&self) -> bool {
+        self.command.is_some_and(|command| command.has(7))
+    }
+
+    fn clock_bit(&mut self, clock: &dyn RtcClock) {
+        if !self.reading() {
+            self.bits_read += 1;
+
+            if self.bits_read == 8 {
+                self.process_byte(clock);
+            }
+
+            return;
+        }
+
+        self.sio = self.output_byte().has(self.bits_read);
+        self.bits_read += 1;
+
+        if self.bits_read == 8 {
+            self.bits_read = 0;
+            self.bytes_remaining -= 1;
+
+            if self.bytes_remaining == 0 {
+                self.command = None;
+            }
+        }
+    }
+
+    fn process_byte(&mut self, clock: &dyn RtcClock) {
+        match self.command {
+            None if self.bits.get_bits(0, 3) == 0b0110 => {
+                let command = self.bits.get_bits(4, 6);
+
+                self.bytes_remaining = COMMAND_BYTES[command as usize];
+                self.command = (self.bytes_remaining > 0).then_some(self.bits);
+
+                match command {
+                    CMD_RESET => self.control = 0,
+                    CMD_DATETIME | CMD_TIME => self.latch_time(clock.now()),
+                    _ => {}
+                }
+            }
+            None => {} // invalid magic, ignored
+            Some(command) => {
+                if command.get_bits(4, 6) == CMD_CONTROL {
+                    self.control = self.bits;
+                }
+
+                self.bytes_remaining = self.bytes_remaining.saturating_sub(1);
+
+                if self.bytes_remaining == 0 {
+                    self.command = None;
+                }
+            }
+        }
+
+        self.bits = 0;
+        self.bits_read = 0;
+    }
+
+    fn output_byte(&self) -> u8 {
+        match self.command.map(|command| command.get_bits(4, 6)) {
+            Some(CMD_CONTROL) => self.control,
+            Some(CMD_DATETIME | CMD_TIME) => self.time[7 - self.bytes_remaining as usize],
+            _ => 0,
+        }
+    }
+
+    fn latch_time(&mut self, timestamp: u64) {
+        let days = timestamp / 86400;
+        let secs = timestamp % 86400;
+        let (year, month, day) = civil_from_days(days);
+        let hour = secs / 3600;
+
+        let hour = match self.control.has(6) {
+            true => bcd(hour),
+            false => bcd(hour % 12) | ((hour >= 12) as u8) << 7,
+        };
+
+        self.time = [
+            bcd(year % 100),
+            bcd(month),
+            bcd(day),
+            bcd((days + 4) % 7), // 1970-01-01 was a thursday
+            hour,
+            bcd(secs / 60 % 60),
+            bcd(secs % 60),
+        ];
+    }
+}
+
+fn bcd(value: u64) -> u8 {
+    (value / 10 * 16 + value % 10) as u8
+}
+
+/// Gregorian date of a day count since the Unix epoch.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as u64;
+
+    (year, month, day)
+}
@@ -0,0 +1,121 @@
+use crate::utils::{bitflags::Bitflag, savestate::impl_savestate};
+
+/// Center of the 12-bit gyro samples.
+const GYRO_CENTER: i32 = 0x6C0;
+/// Center of the 12-bit tilt samples.
+const TILT_CENTER: i32 = 0x3A0;
+
+/// Host input read by the cartridge sensors.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SensorInput {
+    /// Ambient light reaching the solar sensor, 0 being darkness.
+    pub light: u8,
+    /// Rotation rate around the axis perpendicular to the screen, 0 at rest.
+    pub gyro: i16,
+    /// Tilt of the console, 0 when flat.
+    pub tilt_x: i16,
+    pub tilt_y: i16,
+}
+
+/// Boktai photodiode: a counter clocked on pin 0 and reset on pin 1, pin 3 flags once it
+/// reaches the sampled darkness.
+#[derive(Debug, Default)]
+pub struct SolarSensor {
+    counter: u16,
+    sample: u16,
+    edge: bool,
+}
+
+impl_savestate!(SolarSensor {
+    counter,
+    sample,
+    edge
+});
+
+impl SolarSensor {
+    pub fn output(&self) -> u8 {
+        ((self.counter >= self.sample) as u8) << 3
+    }
+
+    pub fn write_pins(&mut self, pins: u8, light: u8) {
+        if pins.has(2) {
+            return; // chip select of the RTC sharing the port
+        }
+
+        if pins.has(1) {
+            self.counter = 0;
+            self.sample = 0xFF - light as u16;
+        }
+
+        if pins.has(0) && self.edge {
+            self.counter = self.counter.saturating_add(1);
+        }
+
+        self.edge = !pins.has(0);
+    }
+}
+
+/// WarioWare Twisted gyro: sampled on pin 0, then shifted out MSB first on pin 2
+/// at each falling edge of pin 1.
+#[derive(Debug, Default)]
+pub struct Gyro {
+    sample: u16,
+    edge: bool,
+    bit: bool,
+}
+
+impl_savestate!(Gyro { sample, edge, bit });
+
+impl Gyro {
+    pub fn output(&self) -> u8 {
+        (self.bit as u8) << 2
+    }
+
+    pub fn write_pins(&mut self, pins: u8, rate: i16) {
+        if pins.has(0) {
+            self.sample = (GYRO_CENTER + (rate as i32 >> 5)) as u16;
+        }
+
+        if self.edge && !pins.has(1) {
+            self.bit = self.sample.has(15);
+            self.sample <<= 1;
+        }
+
+        self.edge = pins.has(1);
+    }
+}
+
+/// Accelerometer of Yoshi Topsy-Turvy and Koro Koro Puzzle, mapped at 0x0E00_8000 in place of SRAM.
+#[derive(Debug, Default)]
+pub struct TiltSensor {
+    armed: bool,
+    x: u16,
+    y: u16,
+}
+
+impl_savestate!(TiltSensor { armed, x, y });
+
+impl TiltSensor {
+    pub fn read(&self, address: u32) -> u8 {
+        match address & 0xFFFF {
+            0x8200 => self.x as u8,
+            0x8300 => (self.x >> 8) as u8 & 0xF | 0x80, // bit 7: sample ready
+            0x8400 => self.y as u8,
+            0x8500 => (self.y >> 8) as u8 & 0xF,
+            _ => 0xFF,
+        }
+    }
+
+    /// Writing 0x55 then 0xAA samples both axes.
+    pub fn write(&mut self, address: u32, value: u8, input: &SensorInput) {
+        match (address & 0xFFFF, value) {
+            (0x8000, 0x55) => self.armed = true,
+            (0x8100, 0xAA) if self.armed => {
+                self.armed = false;
+                self.x = (TILT_CENTER + (input.tilt_x as i32 >> 5)) as u16;
+                self.y = (TILT_CENTER + (input.tilt_y as i32 >> 5)) as u16;
+            }
+            _ => {}
+        }
+    }
+}
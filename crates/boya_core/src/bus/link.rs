@@ -0,0 +1,134 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::bus::registers::sio::SioMode;
+
+pub const LINK_PLAYERS: usize = 4;
+
+/// Data of every player in a single transfer, `None` for an unconnected slot.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LinkFrame {
+    pub mode: SioMode,
+    pub data: [Option<u32>; LINK_PLAYERS],
+}
+
+impl LinkFrame {
+    /// Data sent by the first player other than `player`, the line is pulled high when unconnected.
+    pub fn other(&self, player: usize) -> u32 {
+        self.data
+            .iter()
+            .enumerate()
+            .find_map(|(idx, data)| data.filter(|_| idx != player))
+            .unwrap_or(u32::MAX)
+    }
+}
+
+/// Link cable plugged into the serial port.
+pub trait LinkTransport: Debug + Send {
+    /// Position on the cable, the master being player 0.
+    fn player(&self) -> usize;
+
+    /// Data sent by this end when another player starts a transfer.
+    fn publish(&mut self, data: u32);
+
+    /// Starts a transfer from this end, returning the data of every player.
+    fn transfer(&mut self, mode: SioMode, data: u32) -> LinkFrame;
+
+    /// Transfer started by another player since the last poll.
+    fn poll(&mut self) -> Option<LinkFrame>;
+}
+
+/// Cable plugged back into the same console, every transfer receives the data it sent.
+#[derive(Debug, Default)]
+pub struct Loopback;
+
+impl LinkTransport for Loopback {
+    fn player(&self) -> usize {
+        0
+    }
+
+    fn publish(&mut self, _data: u32) {}
+
+    fn transfer(&mut self, mode: SioMode, data: u32) -> LinkFrame {
+        LinkFrame {
+            mode,
+            data: [Some(data), Some(data), None, None],
+        }
+    }
+
+    fn poll(&mut self) -> Option<LinkFrame> {
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct LinkHub {
+    send: [Option<u32>; LINK_PLAYERS],
+    inbox: [VecDeque<LinkFrame>; LINK_PLAYERS],
+}
+
+/// In-process cable between several `Gba` instances, stepped from one or more threads.
+#[derive(Debug)]
+pub struct LocalLink {
+    player: usize,
+    hub: Arc<Mutex<LinkHub>>,
+}
+
+impl LocalLink {
+    /// Ends of a cable connecting `players` consoles, clamped to 2..=4, the first one is the master.
+    pub fn cable(players: usize) -> Vec<LocalLink> {
+        let players = players.clamp(2, LINK_PLAYERS);
+        let mut hub = LinkHub::default();
+
+        hub.send[..players].fill(Some(u32::MAX));
+
+        let hub = Arc::new(Mutex::new(hub));
+
+        (0..players)
+            .map(|player| LocalLink {
+                player,
+                hub: hub.clone(),
+            })
+            .collect()
+    }
+
+    fn with_hub<T>(&self, f: impl FnOnce(&mut LinkHub) -> T) -> T {
+        f(&mut self.hub.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl LinkTransport for LocalLink {
+    fn player(&self) -> usize {
+        self.player
+    }
+
+    fn publish(&mut self, data: u32) {
+        self.with_hub(|hub| hub.send[self.player] = Some(data));
+    }
+
+    fn transfer(&mut self, mode: SioMode, data: u32) -> LinkFrame {
+        self.with_hub(|hub| {
+            hub.send[self.player] = Some(data);
+
+            let frame = LinkFrame {
+                mode,
+                data: hub.send,
+            };
+
+            for (player, inbox) in hub.inbox.iter_mut().enumerate() {
+                if player != self.player && hub.send[player].is_some() {
+                    inbox.push_back(frame);
+                }
+            }
+
+            frame
+        })
+    }
+
+    fn poll(&mut self) -> Option<LinkFrame> {
+        self.with_hub(|hub| hub.inbox[self.player].pop_front())
+    }
+}
@@ -1,27 +1,35 @@
+pub mod backup;
+pub mod gpio;
+pub mod link;
+pub mod prefetch;
 pub mod registers;
 pub mod types;
 
 use crate::{
     apu::Apu,
     bus::{
+        backup::Backup,
+        gpio::Gpio,
+        prefetch::Prefetch,
         registers::{
             IORegister,
             dma::{
-                DmaAddressControl, DmaData, DmaResult, DmaSpecialTiming, DmaStartTiming, DmaTimer,
+                DMA_START_DELAY, Dma, DmaAddressControl, DmaData, DmaResult, DmaSpecialTiming,
+                DmaStartTiming, DmaTimer, DmaTransfer,
             },
         },
         types::{
             Cycle, DataType, Interrupt, MemoryAccess, MemoryRegion, MemoryRegionData, WaitState,
         },
     },
-    ppu::{Ppu, registers::dispstat::Dispstat},
-    utils::Reset,
+    ppu::Ppu,
+    utils::{Reset, savestate::impl_savestate},
 };
 
 pub const BIOS_SIZE: usize = 0x04000; // 16kb
 pub const IWRAM_SIZE: usize = 0x08000; // 32kb
 pub const EWRAM_SIZE: usize = 0x40000; // 256kb
-pub const SRAM_SIZE: usize = 0x10000; // 64kb
+pub const SRAM_SIZE: usize = 0x08000; // 32kb
 pub const IOREG_SIZE: usize = 0x210;
 
 #[derive(Debug)]
@@ -30,12 +38,37 @@ pub struct GbaBus {
     pub iwram: [u8; IWRAM_SIZE],
     pub ewram: Box<[u8; EWRAM_SIZE]>,
     pub rom: Vec<u8>,
-    pub sram: Box<[u8; SRAM_SIZE]>,
+    /// Small ROMs repeat across the gamepak area instead of returning open bus past their end.
+    pub mirror_rom: bool,
+    pub backup: Backup,
+    pub gpio: Gpio,
     pub io: IORegister,
     pub ppu: Ppu,
     pub apu: Apu,
+    pub prefetch: Prefetch,
+
+    backup_dirty: bool,
+    /// Last prefetched opcode, returned by reads from unmapped memory.
+    open_bus: u32,
+    /// Last opcode fetched from the BIOS, returned by BIOS reads from outside of it.
+    bios_open_bus: u32,
+    executing_bios: bool,
 }
 
+impl_savestate!(GbaBus {
+    iwram,
+    ewram,
+    backup,
+    gpio,
+    io,
+    ppu,
+    apu,
+    prefetch,
+    open_bus,
+    bios_open_bus,
+    executing_bios
+});
+
 impl Default for GbaBus {
     fn default() -> Self {
         Self {
@@ -43,17 +76,26 @@ impl Default for GbaBus {
             iwram: [0; IWRAM_SIZE],
             ewram: Box::new([0; EWRAM_SIZE]),
             rom: Vec::new(),
-            sram: Box::new([0; SRAM_SIZE]),
+            mirror_rom: false,
+            backup: Backup::default(),
+            gpio: Gpio::default(),
             io: IORegister::new(),
             ppu: Ppu::default(),
             apu: Apu::default(),
+            prefetch: Prefetch::default(),
+            backup_dirty: false,
+            open_bus: 0,
+            bios_open_bus: 0,
+            executing_bios: false,
         }
     }
 }
 
 impl GbaBus {
     pub fn tick(&mut self, cycles: u32) {
+        self.tick_prefetch(cycles);
         self.ppu.tick(cycles);
+        self.apu.tick(cycles);
 
         let ovf0 = self.io.timer[0].tick(cycles, false);
         let ovf1 = self.io.timer[1].tick(cycles, ovf0);
@@ -68,6 +110,9 @@ impl GbaBus {
             self.apu.on_timer_overflow(DmaTimer::Timer1);
         }
 
+        self.io.sio.tick(cycles);
+        self.tick_dma(cycles);
+
         let interrupt = self
             .ppu
             .poll_interrupt()
@@ -76,6 +121,26 @@ impl GbaBus {
         if let Some(interrupt) = interrupt {
             self.send_interrupt(interrupt);
         }
+
+        self.io.try_wake();
+    }
+
+    /// The system clock is paused while stopped, only the keypad and a transfer clocked by another
+    /// console can still raise an interrupt.
+    pub fn tick_stopped(&mut self) {
+        self.io.sio.tick(0);
+
+        let interrupt = self
+            .io
+            .sio
+            .poll_interrupt()
+            .or_else(|| self.io.keypad.poll_interrupt());
+
+        if let Some(interrupt) = interrupt {
+            self.send_interrupt(interrupt);
+        }
+
+        self.io.try_wake();
     }
 
     pub fn rw_cycle(&self, addr: u32, dt: DataType, access_kind: MemoryAccess) -> Cycle {
@@ -89,22 +154,178 @@ impl GbaBus {
         }
     }
 
-    // FIXME: currently slow, use slice copy to improve performance
+    /// Reads the opcode at `address` and latches it as the open bus value.
+    pub fn fetch_opcode(&mut self, address: u32, thumb: bool) -> u32 {
+        self.executing_bios = matches!(MemoryRegion::from_address(address), MemoryRegion::BIOS);
+
+        let word = self.read_word(address);
+
+        // THUMB opcodes are mirrored on both halves, except for some 32-bit regions
+        self.open_bus = match thumb {
+            true => (word & 0xFFFF) * 0x0001_0001,
+            false => word,
+        };
+
+        if self.executing_bios {
+            self.bios_open_bus = self.read_word(address & !3);
+        }
+
+        word
+    }
+
+    /// Timing of an opcode fetch, which may be served by the prefetch buffer.
+    pub fn fetch_cycle(&mut self, addr: u32, dt: DataType, access_kind: MemoryAccess) -> Cycle {
+        let cycle = self.rw_cycle(addr, dt, access_kind);
+
+        if !MemoryRegion::from_address(addr).is_gamepak() {
+            return cycle;
+        }
+
+        if !self.io.waitcnt.prefetch_buffer() {
+            self.prefetch.flush();
+            return cycle;
+        }
+
+        let halfwords = u8::max(dt.size() / 2, 1) as u32;
+        let seq = self
+            .rw_cycle(addr, DataType::HWord, MemoryAccess::Seq)
+            .count();
+        let cost = self.prefetch.fetch(addr, halfwords, seq, cycle.count());
+
+        Cycle::new(cost, 0, 0, WaitState::default())
+    }
+
+    /// Timing of a CPU data access, which interrupts prefetching when targeting the cartridge.
+    pub fn data_cycle(&mut self, addr: u32, dt: DataType, access_kind: MemoryAccess) -> Cycle {
+        if MemoryRegion::from_address(addr).is_gamepak() {
+            self.prefetch.flush();
+        }
+
+        self.rw_cycle(addr, dt, access_kind)
+    }
+
+    fn tick_prefetch(&mut self, cycles: u32) {
+        if let Some(head) = self.prefetch.head() {
+            let seq = self
+                .rw_cycle(head, DataType::HWord, MemoryAccess::Seq)
+                .count();
+
+            self.prefetch.tick(cycles, seq);
+        }
+    }
+
+    /// Transfers a single unit of the highest priority channel requesting the bus,
+    /// so a channel triggered mid-transfer preempts the lower priority ones.
     pub fn try_dma(&mut self) -> Option<DmaResult> {
-        let data = self.poll_active_dma()?;
-        let cycles = self.dma_cycles(&data);
+        let channel = self.io.dma.iter().position(Dma::ready)?;
+        let data = self.io.dma[channel].get_data();
+        let mut transfer = self.io.dma[channel].transfer()?.clone();
+
+        if [data.src_addr, data.dst_addr]
+            .iter()
+            .any(|&addr| MemoryRegion::from_address(addr).is_gamepak())
+        {
+            self.prefetch.flush();
+        }
 
-        self.execute_dma(&data);
+        if !transfer.started
+            && self.is_eeprom(data.dst_addr)
+            && let Backup::Eeprom(eeprom) = &mut self.backup
+        {
+            eeprom.detect_width(transfer.remaining);
+        }
+
+        let cycles = self.dma_cycles(&data, &transfer);
+        self.execute_dma(&data, &mut transfer);
+
+        // the other channels lose the bus, they resume with a non-sequential access
+        for (idx, dma) in self.io.dma.iter_mut().enumerate() {
+            if let Some(other) = dma.transfer_mut()
+                && idx != channel
+            {
+                other.seq = false;
+            }
+        }
 
-        if data.irq_enable {
-            self.send_interrupt(data.channel.into());
+        let done = transfer.remaining == 0;
+        let dma = &mut self.io.dma[channel];
+
+        // the transfer may have been aborted by a write to its own control register
+        if let Some(current) = dma.transfer_mut() {
+            *current = transfer;
+
+            if done {
+                dma.complete();
+
+                if data.irq_enable {
+                    self.send_interrupt(data.channel.into());
+                }
+            }
         }
 
         Some(DmaResult { data, cycles })
     }
 
+    /// EEPROM is mapped to the upper 16mb of the ROM area, or its last 256 bytes with a 32mb ROM.
+    fn is_eeprom(&self, address: u32) -> bool {
+        let range = match self.rom.len() {
+            0..=0x0100_0000 => 0x0D00_0000..=0x0DFF_FFFF,
+            _ => 0x0DFF_FF00..=0x0DFF_FFFF,
+        };
+
+        matches!(self.backup, Backup::Eeprom(_)) && range.contains(&address)
+    }
+
+    /// Takes over what save states leave out, the BIOS, the cartridge and the host devices, from
+    /// the bus this one replaces.
+    pub fn inherit(&mut self, mut previous: GbaBus) {
+        self.bios = previous.bios;
+        self.rom = previous.rom;
+        self.mirror_rom = previous.mirror_rom;
+        self.backup_dirty = previous.backup_dirty;
+        self.gpio.inherit(previous.gpio);
+        self.apu.resampler = previous.apu.resampler;
+
+        if let Some(link) = previous.io.sio.disconnect() {
+            self.io.sio.connect(link);
+        }
+    }
+
+    /// Whether the backup memory was written since the last poll.
+    pub fn poll_backup_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.backup_dirty, false)
+    }
+
+    /// Halfword read by the CPU, which shifts a bit out of the EEPROM like a DMA read does.
+    pub fn load_hword(&mut self, address: u32) -> u16 {
+        match self.is_eeprom(address) {
+            true => self.read_eeprom(),
+            false => self.read_hword(address),
+        }
+    }
+
+    fn read_eeprom(&mut self) -> u16 {
+        match &mut self.backup {
+            Backup::Eeprom(eeprom) => eeprom.read(),
+            _ => 0,
+        }
+    }
+
+    /// Past the end of the cartridge, the address bus holds `address / 2` as a halfword.
     fn read_rom(&self, address: usize) -> u8 {
-        self.rom.get(address).copied().unwrap_or_default()
+        let offset = match self.mirror_rom {
+            true => address & (self.rom.len().next_power_of_two() - 1),
+            false => address,
+        };
+
+        self.rom
+            .get(offset)
+            .copied()
+            .unwrap_or_else(|| ((address >> 1) as u16).to_le_bytes()[address & 1])
+    }
+
+    fn read_open_bus(&self, address: u32) -> u8 {
+        self.open_bus.to_le_bytes()[address as usize & 3]
     }
 
     fn region_data(&self, region: MemoryRegion) -> MemoryRegionData {
@@ -119,102 +340,123 @@ impl GbaBus {
             MemoryRegion::WaitState0 => (DataType::HWord, self.io.waitcnt.wait_state0()),
             MemoryRegion::WaitState1 => (DataType::HWord, self.io.waitcnt.wait_state1()),
             MemoryRegion::WaitState2 => (DataType::HWord, self.io.waitcnt.wait_state2()),
-            MemoryRegion::SRAM => (DataType::HWord, self.io.waitcnt.sram_wait()), // FIXME: Detect save type SRAM/FLASH/EEPROM
+            MemoryRegion::SRAM => (DataType::HWord, self.io.waitcnt.sram_wait()),
             _ => (DataType::Word, WaitState::default()),
         };
 
         MemoryRegionData { width, waitstate }
     }
 
-    fn execute_dma(&mut self, dma: &DmaData) {
-        let mut src_addr = dma.src_addr;
-        let mut dst_addr = dma.dst_addr;
-
+    fn execute_dma(&mut self, dma: &DmaData, transfer: &mut DmaTransfer) {
         let dma_dt = dma.transfer_type;
         let chunk_size = dma_dt.size() as u32;
 
-        for _ in 0..dma.transfer_len {
-            match dma_dt {
-                DataType::Word => {
-                    let word = self.read_word(src_addr);
-                    self.write_word(dst_addr, word);
-                }
-                _ => {
-                    let hword = self.read_hword(src_addr);
-                    self.write_hword(dst_addr, hword);
-                }
+        match dma_dt {
+            DataType::Word => {
+                let word = self.read_word(transfer.src_addr);
+                self.write_word(transfer.dst_addr, word);
+            }
+            _ => {
+                let hword = if self.is_eeprom(transfer.src_addr) {
+                    self.read_eeprom()
+                } else {
+                    self.read_hword(transfer.src_addr)
+                };
+
+                self.write_hword(transfer.dst_addr, hword);
             }
+        }
 
-            match dma.dst_addr_ctrl {
-                DmaAddressControl::Increment => dst_addr += chunk_size,
-                DmaAddressControl::Decrement => dst_addr -= chunk_size,
-                _ => {}
+        transfer.dst_addr = match dma.dst_addr_ctrl {
+            DmaAddressControl::Decrement => transfer.dst_addr.wrapping_sub(chunk_size),
+            DmaAddressControl::Increment | DmaAddressControl::IncrementReload => {
+                transfer.dst_addr.wrapping_add(chunk_size)
             }
+            DmaAddressControl::Fixed => transfer.dst_addr,
+        } & dma.channel.dst_mask();
 
-            match dma.src_addr_ctrl {
-                DmaAddressControl::Decrement => src_addr -= chunk_size,
-                DmaAddressControl::Increment | DmaAddressControl::IncrementReload => {
-                    src_addr += chunk_size
-                }
-                DmaAddressControl::Fixed => {}
+        transfer.src_addr = match dma.src_addr_ctrl {
+            DmaAddressControl::Decrement => transfer.src_addr.wrapping_sub(chunk_size),
+            DmaAddressControl::Increment | DmaAddressControl::IncrementReload => {
+                transfer.src_addr.wrapping_add(chunk_size)
             }
-        }
+            DmaAddressControl::Fixed => transfer.src_addr,
+        } & dma.channel.src_mask();
+
+        transfer.remaining -= 1;
+        transfer.started = true;
+        transfer.seq = true;
     }
 
-    fn dma_cycles(&self, dma: &DmaData) -> Cycle {
+    /// Cycles of a single unit, the first one also pays the internal start-up cycles.
+    fn dma_cycles(&self, dma: &DmaData, transfer: &DmaTransfer) -> Cycle {
         let dma_dt = dma.transfer_type;
-        let src_region = MemoryRegion::from_address(dma.src_addr);
-        let dst_region = MemoryRegion::from_address(dma.dst_addr);
+        let access = match transfer.seq {
+            true => MemoryAccess::Seq,
+            false => MemoryAccess::NonSeq,
+        };
 
-        let read_seq = self.rw_cycle(dma.src_addr, dma_dt, MemoryAccess::Seq);
-        let write_seq = self.rw_cycle(dma.dst_addr, dma_dt, MemoryAccess::Seq);
-        let read_non_seq = self.rw_cycle(dma.src_addr, dma_dt, MemoryAccess::NonSeq);
-        let write_non_seq = self.rw_cycle(dma.dst_addr, dma_dt, MemoryAccess::NonSeq);
+        let read_cycles = self.rw_cycle(dma.src_addr, dma_dt, access);
+        let write_cycles = self.rw_cycle(dma.dst_addr, dma_dt, access);
 
-        let read_cycles = read_non_seq + read_seq.repeat(dma.transfer_len - 1);
-        let write_cycles = write_non_seq + write_seq.repeat(dma.transfer_len - 1);
+        let src_region = MemoryRegion::from_address(dma.src_addr);
+        let dst_region = MemoryRegion::from_address(dma.dst_addr);
 
-        let internal_cycles = match true {
-            _ if src_region.is_gamepak() && dst_region.is_gamepak() => Cycle::internal(4),
-            _ => Cycle::internal(2),
+        let internal_cycles = match transfer.started {
+            true => Cycle::internal(0),
+            false if src_region.is_gamepak() && dst_region.is_gamepak() => Cycle::internal(4),
+            false => Cycle::internal(2),
         };
 
-        read_cycles + write_cycles + internal_cycles // 2N + 2(n-1)S + xI
+        read_cycles + write_cycles + internal_cycles // 2N + 2(n-1)S + xI over the transfer
     }
 
-    fn poll_active_dma(&mut self) -> Option<DmaData> {
-        for channel in 0..self.io.dma.len() {
-            if self.should_start_dma(channel) {
-                let dma = &mut self.io.dma[channel];
+    /// Counts down the start-up delays and triggers the channels whose start condition was met.
+    fn tick_dma(&mut self, cycles: u32) {
+        let hblank = self.ppu.poll_hblank_dma();
+        let vblank = self.ppu.poll_vblank_dma();
+        let capture = self.ppu.poll_capture_dma();
 
-                if !dma.repeat() {
-                    dma.disable();
-                }
+        if self.ppu.poll_capture_end() {
+            let dma3 = &mut self.io.dma[3];
 
-                return Some(dma.get_data());
+            if dma3.dma_enable() && dma3.video_capture() {
+                dma3.disable();
             }
         }
 
-        None
+        for channel in 0..self.io.dma.len() {
+            self.io.dma[channel].tick(cycles);
+
+            if self.should_start_dma(channel, hblank, vblank, capture) {
+                self.io.dma[channel].start(DMA_START_DELAY);
+            }
+        }
     }
 
-    fn should_start_dma(&mut self, channel: usize) -> bool {
+    fn should_start_dma(
+        &mut self,
+        channel: usize,
+        hblank: bool,
+        vblank: bool,
+        capture: bool,
+    ) -> bool {
         let dma = &self.io.dma[channel];
 
-        if !dma.dma_enable() {
+        if !dma.dma_enable() || dma.active() {
             return false;
         }
 
         match dma.start_timing() {
-            DmaStartTiming::Immediate => true, // FIXME: should wait 2 cycles?
-            DmaStartTiming::VBlank => self.ppu.registers.dispstat.has(Dispstat::VBLANK),
-            DmaStartTiming::HBlank => self.ppu.registers.dispstat.has(Dispstat::HBLANK),
+            DmaStartTiming::Immediate => true,
+            DmaStartTiming::VBlank => vblank,
+            DmaStartTiming::HBlank => hblank,
 
             DmaStartTiming::Special => match dma.special_timing() {
                 DmaSpecialTiming::None => true, // immediate
                 DmaSpecialTiming::FifoA => self.apu.poll_fifo_a_request(),
                 DmaSpecialTiming::FifoB => self.apu.poll_fifo_b_request(),
-                DmaSpecialTiming::VideoCapture => todo!(), // TODO: Video capture DMA start
+                DmaSpecialTiming::VideoCapture => capture,
             },
         }
     }
@@ -242,18 +484,35 @@ impl GbaBus {
 impl Bus for GbaBus {
     fn read_byte(&self, address: u32) -> u8 {
         match address {
-            0x0000_0000..=0x0000_3FFF => self.bios[address as usize],
+            0x0000_0000..=0x0000_3FFF if self.executing_bios => self.bios[address as usize],
+            0x0000_0000..=0x0000_3FFF => self.bios_open_bus.to_le_bytes()[address as usize & 3],
             0x0200_0000..=0x02FF_FFFF => self.ewram[address as usize & 0x3FFFF],
             0x0300_0000..=0x03FF_FFFF => self.iwram[address as usize & 0x7FFF],
-            0x0400_0000..=0x0400_005F => self.ppu.registers.read_byte(address),
+            0x0400_0000..=0x0400_005F => self
+                .ppu
+                .registers
+                .read(address)
+                .unwrap_or_else(|| self.read_open_bus(address)),
             0x0400_0060..=0x0400_00AF => self.apu.registers.read_byte(address),
-            0x0400_00B0..=0x04FF_FFFF => self.io.read_byte(address),
+            0x0400_00B0..=0x04FF_FFFF => self
+                .io
+                .read(address)
+                .unwrap_or_else(|| self.read_open_bus(address)),
             0x0500_0000..=0x05FF_FFFF => self.ppu.palette[address as usize & 0x3FF],
             0x0600_0000..=0x06FF_FFFF => self.ppu.read_vram(address),
             0x0700_0000..=0x07FF_FFFF => self.ppu.oam[address as usize & 0x3FF],
+            0x0D00_0000..=0x0DFF_FFFF if self.is_eeprom(address) => match &self.backup {
+                Backup::Eeprom(eeprom) if address % 2 == 0 => eeprom.peek() as u8,
+                _ => 0,
+            },
+            0x0800_00C4..=0x0800_00C9 => self
+                .gpio
+                .read(address)
+                .unwrap_or_else(|| self.read_rom(address as usize & 0x01FF_FFFF)),
             0x0800_0000..=0x0DFF_FFFF => self.read_rom(address as usize & 0x01FF_FFFF),
-            0x0E00_0000..=0x0FFF_FFFF => self.sram[address as usize & 0x0FFFF],
-            _ => 0x0, // TODO: open bus
+            0x0E00_8000..=0x0E00_85FF if self.gpio.has_tilt() => self.gpio.read_tilt(address),
+            0x0E00_0000..=0x0FFF_FFFF => self.backup.read(address),
+            _ => self.read_open_bus(address),
         }
     }
 
@@ -267,7 +526,18 @@ impl Bus for GbaBus {
             0x0500_0000..=0x05FF_FFFF => self.ppu.palette[address as usize & 0x3FF] = value,
             0x0600_0000..=0x06FF_FFFF => self.ppu.write_vram(address, value),
             0x0700_0000..=0x07FF_FFFF => self.ppu.oam[address as usize & 0x3FF] = value,
-            0x0E00_0000..=0x0FFF_FFFF => self.sram[address as usize & 0x0FFFF] = value,
+            0x0D00_0000..=0x0DFF_FFFF if self.is_eeprom(address) => {
+                if let Backup::Eeprom(eeprom) = &mut self.backup
+                    && address % 2 == 0
+                {
+                    self.backup_dirty |= eeprom.write(value);
+                }
+            }
+            0x0800_00C4..=0x0800_00C9 => self.gpio.write(address, value),
+            0x0E00_8000..=0x0E00_85FF if self.gpio.has_tilt() => {
+                self.gpio.write_tilt(address, value)
+            }
+            0x0E00_0000..=0x0FFF_FFFF => self.backup_dirty |= self.backup.write(address, value),
             _ => {}
         };
     }
@@ -277,10 +547,18 @@ impl Reset for GbaBus {
     fn reset(&mut self) {
         self.iwram.fill(0);
         self.ewram.fill(0);
-        self.sram.fill(0);
+        self.backup.reset();
+        self.gpio.reset();
+
+        let link = self.io.sio.disconnect();
         self.io = IORegister::new();
+
+        if let Some(link) = link {
+            self.io.sio.connect(link);
+        }
         self.ppu.reset();
-        self.apu.reset()
+        self.apu.reset();
+        self.prefetch = Prefetch::default();
     }
 }
 
@@ -363,7 +641,17 @@ impl<const N: usize> Bus for [u8; N] {
 
 #[cfg(test)]
 mod tests {
-    use crate::{assert_snapshot, test::GbaTestBuilder};
+    use crate::{
+        assert_snapshot,
+        bus::{
+            Bus, GbaBus,
+            backup::Backup,
+            link::{LinkTransport, LocalLink},
+            registers::sio::SioMode,
+        },
+        rom::SaveType,
+        test::GbaTestBuilder,
+    };
 
     #[test]
     fn test_bios_cycle_count() {
@@ -382,4 +670,218 @@ mod tests {
 
         assert_snapshot!(snapshot);
     }
+
+    #[test]
+    fn test_open_bus() {
+        let mut bus = GbaBus::default();
+
+        bus.bios[..4].copy_from_slice(&[0x01, 0x02, 0x03, 0x04]);
+        bus.rom = vec![0xAA; 4];
+
+        bus.fetch_opcode(0x0000_0000, false);
+        assert_eq!(bus.read_word(0x0000_0000), 0x0403_0201);
+
+        bus.fetch_opcode(0x0800_0000, false);
+        assert_eq!(bus.read_word(0x0000_0010), 0x0403_0201, "BIOS protection");
+        assert_eq!(bus.read_word(0x0100_0000), 0xAAAA_AAAA, "unmapped");
+        assert_eq!(bus.read_hword(0x0400_0010), 0xAAAA, "write-only BG0HOFS");
+        assert_eq!(bus.read_hword(0x0400_1000), 0xAAAA, "unused I/O");
+        assert_eq!(bus.read_hword(0x0400_0054), 0xAAAA, "write-only BLDY");
+        assert_eq!(bus.read_hword(0x0400_00D4), 0xAAAA, "write-only DMA3SAD");
+
+        bus.write_hword(0x0400_0052, 0x0C0A);
+        bus.write_hword(0x0400_0208, 1);
+        assert_eq!(bus.read_hword(0x0400_0052), 0x0C0A, "BLDALPHA");
+        assert_eq!(bus.read_word(0x0400_0208), 1, "IME, upper half zero");
+        assert_eq!(bus.read_word(0x0400_0204) >> 16, 0, "unused 0x206");
+
+        for address in [0x0B8, 0x0C4, 0x0D0, 0x0DC, 0x136, 0x142, 0x15A] {
+            assert_eq!(
+                bus.read_hword(0x0400_0000 + address),
+                0,
+                "zero at {address:#X}"
+            );
+        }
+        assert_eq!(
+            bus.read_hword(0x0800_1000),
+            0x0800,
+            "past the end of the ROM"
+        );
+
+        bus.fetch_opcode(0x0800_0002, true);
+        assert_eq!(
+            bus.read_word(0x1000_0000),
+            0xAAAA_AAAA,
+            "THUMB opcode mirrored"
+        );
+    }
+
+    #[test]
+    fn test_rom_mirroring() {
+        let mut bus = GbaBus {
+            rom: (0..6).collect(),
+            mirror_rom: true,
+            ..Default::default()
+        };
+
+        assert_eq!(bus.read_word(0x0800_0008), 0x0302_0100);
+        assert_eq!(bus.read_hword(0x0900_000C), 0x0504, "mirrored gamepak area");
+        assert_eq!(
+            bus.read_hword(0x0800_0006),
+            0x0003,
+            "past the end of the ROM"
+        );
+
+        bus.backup = Backup::new(SaveType::Eeprom, Some(0x200));
+        assert_eq!(bus.read_hword(0x0D00_0000), 1, "EEPROM ready bit");
+        assert_eq!(bus.read_hword(0x0C00_0000), 0x0100);
+    }
+
+    #[test]
+    fn test_eeprom_cpu_read() {
+        let mut bus = GbaBus {
+            backup: Backup::new(SaveType::Eeprom, Some(0x200)),
+            ..Default::default()
+        };
+
+        // read request for block 0: 2 command bits, 6 address bits and a stop bit
+        for bit in [1, 1, 0, 0, 0, 0, 0, 0, 0] {
+            bus.write_hword(0x0D00_0000, bit);
+        }
+
+        assert_eq!(bus.read_hword(0x0D00_0000), 0, "peeking doesn't shift");
+        assert_eq!(bus.read_hword(0x0D00_0000), 0);
+
+        let bits = (0..5)
+            .map(|_| bus.load_hword(0x0D00_0000))
+            .collect::<Vec<_>>();
+        assert_eq!(bits, [0, 0, 0, 0, 1], "4 dummy bits before the data");
+    }
+
+    #[test]
+    fn test_dma_preemption() {
+        let mut bus = GbaBus::default();
+
+        for (i, byte) in bus.ewram[..0x104].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        // DMA3: 8 halfwords from EWRAM to IWRAM, immediate
+        bus.write_word(0x0400_00D4, 0x0200_0000);
+        bus.write_word(0x0400_00D8, 0x0300_0000);
+        bus.write_word(0x0400_00DC, 0x8000_0008);
+        bus.tick(1);
+
+        assert!(bus.try_dma().is_none(), "start-up delay");
+        bus.tick(2);
+
+        let mut order = Vec::new();
+        let mut step = |bus: &mut GbaBus| {
+            let result = bus.try_dma()?;
+
+            bus.tick(result.cycles.count());
+            order.push(result.data.channel);
+            Some(())
+        };
+
+        step(&mut bus);
+
+        // DMA0: 2 halfwords, triggered in the middle of DMA3
+        bus.write_word(0x0400_00B0, 0x0200_0100);
+        bus.write_word(0x0400_00B4, 0x0300_0100);
+        bus.write_word(0x0400_00B8, 0x8000_0002);
+        bus.tick(1);
+
+        while step(&mut bus).is_some() {}
+
+        let order = order.iter().map(|&c| c as u8).collect::<Vec<_>>();
+        assert_eq!(order, [3, 3, 0, 0, 3, 3, 3, 3, 3, 3]);
+        assert_eq!(&bus.iwram[..16], &bus.ewram[..16]);
+        assert_eq!(&bus.iwram[0x100..0x104], &bus.ewram[0x100..0x104]);
+        assert!(!bus.io.dma[0].dma_enable() && !bus.io.dma[3].dma_enable());
+    }
+
+    #[test]
+    fn test_video_capture_dma() {
+        let mut bus = GbaBus::default();
+
+        // DMA3: 1 word per line from EWRAM to IWRAM, video capture
+        bus.write_word(0x0400_00D4, 0x0200_0000);
+        bus.write_word(0x0400_00D8, 0x0300_0000);
+        bus.write_word(0x0400_00DC, 0xB600_0001);
+
+        let mut lines = Vec::new();
+
+        while bus.ppu.scanline != 163 {
+            match bus.try_dma() {
+                Some(result) => {
+                    lines.push(bus.ppu.scanline);
+                    bus.tick(result.cycles.count());
+                }
+                None => bus.tick(4),
+            }
+        }
+
+        assert_eq!(lines, (2..=161).collect::<Vec<_>>());
+        assert!(!bus.io.dma[3].dma_enable(), "stopped at line 162");
+    }
+
+    #[test]
+    fn test_dma_repeat_latches() {
+        let mut bus = GbaBus::default();
+
+        for (i, byte) in bus.ewram[..8].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        // DMA0: 2 halfwords per HBlank, repeat, increment/reload destination
+        bus.write_word(0x0400_00B0, 0x0200_0000);
+        bus.write_word(0x0400_00B4, 0x0300_0000);
+        bus.write_word(0x0400_00B8, 0xA260_0002);
+        bus.write_word(0x0400_00B0, 0x0200_0100); // ignored until re-enabled
+
+        while bus.ppu.scanline != 2 {
+            match bus.try_dma() {
+                Some(result) => bus.tick(result.cycles.count()),
+                None => bus.tick(4),
+            }
+        }
+
+        let data = bus.io.dma[0].get_data();
+        assert_eq!(
+            &bus.iwram[..4],
+            &bus.ewram[4..8],
+            "continued from the latch"
+        );
+        assert_eq!(data.src_addr, 0x0200_0008);
+        assert_eq!(data.dst_addr, 0x0300_0004);
+
+        bus.write_word(0x0400_00B8, 0);
+        bus.write_word(0x0400_00B0, 0x0800_0000);
+        bus.write_word(0x0400_00B8, 0xA260_0002);
+        assert_eq!(
+            bus.io.dma[0].get_data().src_addr,
+            0x0000_0000,
+            "DMA0 can't read the gamepak"
+        );
+    }
+
+    #[test]
+    fn test_serial_stop_wake() {
+        let mut cable = LocalLink::cable(2);
+        let mut bus = GbaBus::default();
+
+        bus.io.sio.connect(Box::new(cable.pop().unwrap()));
+        bus.write_hword(0x0400_0128, 0x5080); // 32bit, external clock, IRQ, start
+        bus.write_hword(0x0400_0200, 0x80); // serial IRQ
+        bus.write_hword(0x0400_0208, 1);
+        bus.write_byte(0x0400_0301, 0x80); // STOP
+
+        bus.tick_stopped();
+        assert!(bus.io.stopped());
+
+        cable[0].transfer(SioMode::Normal32, 0x1234_5678);
+        bus.tick_stopped();
+        assert!(!bus.io.stopped(), "woken up by the master clock");
+    }
 }
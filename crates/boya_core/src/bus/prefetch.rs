@@ -0,0 +1,118 @@
+use crate::utils::savestate::impl_savestate;
+
+pub const PREFETCH_CAPACITY: u32 = 8; // halfwords
+
+/// Gamepak prefetch buffer, loads sequential opcodes while the cartridge bus is idle.
+#[derive(Debug, Default)]
+pub struct Prefetch {
+    /// Address of the next halfword requested by the CPU, `None` while stopped.
+    head: Option<u32>,
+    /// Halfwords buffered from `head` onwards.
+    count: u32,
+    /// Cycles spent loading the next halfword, negative while the bus is still busy.
+    progress: i32,
+}
+
+impl_savestate!(Prefetch {
+    head,
+    count,
+    progress
+});
+
+impl Prefetch {
+    pub fn head(&self) -> Option<u32> {
+        self.head
+    }
+
+    pub fn len(&self) -> u32 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Opcode fetch of `halfwords` at `address`, `seq` being the cost of a sequential halfword load.
+    /// Returns the cycles taken by the fetch given the regular `cost` of the access.
+    pub fn fetch(&mut self, address: u32, halfwords: u32, seq: u32, cost: u32) -> u32 {
+        let cost = match self.head {
+            Some(head) if head == address && self.count >= halfwords => {
+                self.count -= halfwords;
+                self.head = Some(address + halfwords * 2);
+                return 1;
+            }
+            Some(head) if head == address => {
+                let missing = halfwords - self.count;
+                let pending = self.progress.max(0) as u32;
+
+                (missing * seq).saturating_sub(pending).max(1)
+            }
+            _ => cost,
+        };
+
+        self.head = Some(address + halfwords * 2);
+        self.count = 0;
+        self.progress = -(cost as i32);
+        cost
+    }
+
+    /// Advances the buffer by the cycles elapsed on the system bus.
+    pub fn tick(&mut self, cycles: u32, seq: u32) {
+        self.progress += cycles as i32;
+
+        while self.count < PREFETCH_CAPACITY && self.progress >= seq as i32 {
+            self.progress -= seq as i32;
+            self.count += 1;
+        }
+
+        if self.count == PREFETCH_CAPACITY {
+            self.progress = self.progress.min(0);
+        }
+    }
+
+    /// Data accesses to the cartridge discard the buffer.
+    pub fn flush(&mut self) {
+        self.head = None;
+        self.count = 0;
+        self.progress = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::prefetch::{PREFETCH_CAPACITY, Prefetch};
+
+    #[test]
+    fn test_prefetch_buffer() {
+        let mut prefetch = Prefetch::default();
+
+        assert_eq!(prefetch.fetch(0x0800_0000, 1, 4, 5), 5, "miss");
+
+        prefetch.tick(5 + 8, 4);
+        assert_eq!(prefetch.len(), 2, "filled after the fetch completed");
+
+        assert_eq!(
+            prefetch.fetch(0x0800_0002, 2, 4, 8),
+            1,
+            "ARM opcode from buffer"
+        );
+        assert!(prefetch.is_empty());
+
+        prefetch.tick(1 + 1, 4);
+        assert_eq!(
+            prefetch.fetch(0x0800_0006, 1, 4, 4),
+            2,
+            "in-flight halfword"
+        );
+
+        prefetch.tick(100, 4);
+        assert_eq!(prefetch.len(), PREFETCH_CAPACITY);
+
+        prefetch.flush();
+        assert_eq!(
+            prefetch.fetch(0x0800_0008, 1, 4, 5),
+            5,
+            "flushed by data access"
+        );
+    }
+}
@@ -3,9 +3,12 @@ use crate::{
         Bus,
         types::{Cycle, DataType, Interrupt},
     },
-    utils::bitflags::Bitflag,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
 };
 
+/// Cycles between a start condition and the first transfer, the CPU keeps running meanwhile.
+pub const DMA_START_DELAY: u32 = 2;
+
 #[derive(Debug, Default, Clone)]
 pub struct Dma {
     pub sad: u32,
@@ -13,8 +16,39 @@ pub struct Dma {
     pub cnt_l: u16,
     pub cnt_h: u16,
     pub channel: DmaChannel,
+
+    /// Internal source address, loaded from `sad` on enable and kept across repeats.
+    src_latch: u32,
+    /// Internal destination address, loaded from `dad` on enable and kept across repeats.
+    dst_latch: u32,
+    /// Triggered transfer, `None` while the channel is idle.
+    transfer: Option<DmaTransfer>,
 }
 
+/// Progress of a triggered transfer, advanced one unit at a time by the bus.
+#[derive(Debug, Default, Clone)]
+pub struct DmaTransfer {
+    pub src_addr: u32,
+    pub dst_addr: u32,
+    /// Units left to transfer.
+    pub remaining: u32,
+    /// Cycles left before the first unit can be transferred.
+    pub delay: u32,
+    /// Whether the internal cycles of the start-up were already spent.
+    pub started: bool,
+    /// Whether the next unit is a sequential access, cleared when preempted.
+    pub seq: bool,
+}
+
+impl_savestate!(DmaTransfer {
+    src_addr,
+    dst_addr,
+    remaining,
+    delay,
+    started,
+    seq
+});
+
 impl Dma {
     pub fn new(channel: DmaChannel) -> Self {
         Self {
@@ -71,6 +105,62 @@ impl Dma {
 
     pub fn disable(&mut self) {
         self.cnt_h.clear(15);
+        self.transfer = None;
+    }
+
+    pub fn transfer(&self) -> Option<&DmaTransfer> {
+        self.transfer.as_ref()
+    }
+
+    pub fn transfer_mut(&mut self) -> Option<&mut DmaTransfer> {
+        self.transfer.as_mut()
+    }
+
+    /// Whether a transfer was triggered and hasn't completed yet.
+    pub fn active(&self) -> bool {
+        self.transfer.is_some()
+    }
+
+    /// Whether the start-up delay elapsed and the channel requests the bus.
+    pub fn ready(&self) -> bool {
+        self.transfer.as_ref().is_some_and(|t| t.delay == 0)
+    }
+
+    /// Triggers a transfer from the latched addresses, starting after `delay` cycles.
+    /// Only the count is reloaded from the registers, along with `dad` for increment/reload.
+    pub fn start(&mut self, delay: u32) {
+        if matches!(self.dst_addr_control(), DmaAddressControl::IncrementReload) {
+            self.dst_latch = self.dad & self.channel.dst_mask();
+        }
+
+        let data = self.get_data();
+
+        self.transfer = Some(DmaTransfer {
+            src_addr: data.src_addr,
+            dst_addr: data.dst_addr,
+            remaining: data.transfer_len,
+            delay,
+            started: false,
+            seq: false,
+        });
+    }
+
+    /// Ends the current transfer, keeping the channel enabled only when repeating.
+    pub fn complete(&mut self) {
+        if let Some(transfer) = self.transfer.take() {
+            self.src_latch = transfer.src_addr;
+            self.dst_latch = transfer.dst_addr;
+        }
+
+        if !self.repeat() || matches!(self.start_timing(), DmaStartTiming::Immediate) {
+            self.disable();
+        }
+    }
+
+    pub fn tick(&mut self, cycles: u32) {
+        if let Some(transfer) = &mut self.transfer {
+            transfer.delay = transfer.delay.saturating_sub(cycles);
+        }
     }
 
     pub fn transfer_len(&self) -> u32 {
@@ -86,15 +176,44 @@ impl Dma {
         self.channel.special_timing()
     }
 
+    pub fn sound_fifo(&self) -> bool {
+        matches!(self.start_timing(), DmaStartTiming::Special)
+            && matches!(
+                self.special_timing(),
+                DmaSpecialTiming::FifoA | DmaSpecialTiming::FifoB
+            )
+    }
+
+    pub fn video_capture(&self) -> bool {
+        matches!(self.start_timing(), DmaStartTiming::Special)
+            && matches!(self.special_timing(), DmaSpecialTiming::VideoCapture)
+    }
+
+    /// Transfer parameters, with the addresses of the transfer in progress if any.
     pub fn get_data(&self) -> DmaData {
+        // Sound DMA always transfers 4 words to a fixed address
+        let (dst_addr_ctrl, transfer_type, transfer_len) = match self.sound_fifo() {
+            true => (DmaAddressControl::Fixed, DataType::Word, 4),
+            false => (
+                self.dst_addr_control(),
+                self.transfer_type(),
+                self.transfer_len(),
+            ),
+        };
+
+        let (src_addr, dst_addr) = match &self.transfer {
+            Some(transfer) => (transfer.src_addr, transfer.dst_addr),
+            None => (self.src_latch, self.dst_latch),
+        };
+
         DmaData {
             channel: self.channel,
-            src_addr: self.sad,
-            dst_addr: self.dad,
+            src_addr,
+            dst_addr,
             src_addr_ctrl: self.src_addr_control(),
-            dst_addr_ctrl: self.dst_addr_control(),
-            transfer_type: self.transfer_type(),
-            transfer_len: self.transfer_len(),
+            dst_addr_ctrl,
+            transfer_type,
+            transfer_len,
             irq_enable: self.irq_enable(),
             timing: self.start_timing(),
             repeat: self.repeat(),
@@ -102,6 +221,16 @@ impl Dma {
     }
 }
 
+impl_savestate!(Dma {
+    sad,
+    dad,
+    cnt_l,
+    cnt_h,
+    src_latch,
+    dst_latch,
+    transfer
+});
+
 impl Bus for Dma {
     fn read_byte(&self, address: u32) -> u8 {
         match address % 12 {
@@ -115,7 +244,19 @@ impl Bus for Dma {
             0..=3 => self.sad.write_byte(address, value),
             4..=7 => self.dad.write_byte(address, value),
             8..=9 => self.cnt_l.write_byte(address, value),
-            _ => self.cnt_h.write_byte(address, value),
+            _ => {
+                let enabled = self.dma_enable();
+                self.cnt_h.write_byte(address, value);
+
+                match (enabled, self.dma_enable()) {
+                    (false, true) => {
+                        self.src_latch = self.sad & self.channel.src_mask();
+                        self.dst_latch = self.dad & self.channel.dst_mask();
+                    }
+                    (_, false) => self.transfer = None,
+                    _ => {}
+                }
+            }
         }
     }
 }
@@ -130,6 +271,22 @@ pub enum DmaChannel {
 }
 
 impl DmaChannel {
+    /// DMA0 is limited to internal memory, the others can read from the gamepak.
+    pub fn src_mask(self) -> u32 {
+        match self {
+            DmaChannel::Dma0 => 0x07FF_FFFF,
+            _ => 0x0FFF_FFFF,
+        }
+    }
+
+    /// Only DMA3 can write to the gamepak.
+    pub fn dst_mask(self) -> u32 {
+        match self {
+            DmaChannel::Dma3 => 0x0FFF_FFFF,
+            _ => 0x07FF_FFFF,
+        }
+    }
+
     pub fn special_timing(self) -> DmaSpecialTiming {
         match self {
             DmaChannel::Dma0 => DmaSpecialTiming::None,
@@ -200,22 +357,22 @@ mod tests {
             start:
                 ; set source address to _chunk_to_copy
                 MOV     R0, #0x0400_0000
-                MOV     R1, #0xB0
+                MOV     R1, #0xD4
                 ADR     R2, _chunk_to_copy
                 STR     R2, [R0, R1]
 
                 ; set destination address to IWRAM
-                MOV     R1, #0xB4
+                MOV     R1, #0xD8
                 MOV     R2, #0x0300_0000
                 STR     R2, [R0, R1]
 
                 ; set transfer length to 8
-                MOV     R1, #0xB8
+                MOV     R1, #0xDC
                 MOV     R2, #0x8
                 STRH    R2, [R0, R1]
 
-                ; Start DMA (16bit, immediate)
-                MOV     R1, #0xBA
+                ; Start DMA3 (16bit, immediate), DMA0 can't read from the gamepak
+                MOV     R1, #0xDE
                 MOV     R2, #0x0
                 ORR     R2, #(1 shl 14) ; set irq enable
                 ORR     R2, #(1 shl 15) ; set dma enable
@@ -232,19 +389,19 @@ mod tests {
             .asm(asm)
             .setup(|cpu| {
                 cpu.bus.io.enable_master_irq();
-                cpu.bus.io.enable_irq(Interrupt::Dma0);
+                cpu.bus.io.enable_irq(Interrupt::Dma3);
             })
             .assert_fn(move |cpu| {
-                let dma0 = &cpu.bus.io.dma[0];
+                let dma3 = &cpu.bus.io.dma[3];
 
-                assert_eq!(0x0800_0004, dma0.sad, "DMA0 source address");
-                assert_eq!(0x0300_0000, dma0.dad, "DMA0 destination address");
-                assert_eq!(8, dma0.transfer_len(), "DMA0 transfer length");
+                assert_eq!(0x0800_0004, dma3.sad, "DMA3 source address");
+                assert_eq!(0x0300_0000, dma3.dad, "DMA3 destination address");
+                assert_eq!(8, dma3.transfer_len(), "DMA3 transfer length");
                 assert_eq!(&cpu.bus.iwram[..16], &expected_chunks);
-                assert!(!dma0.dma_enable(), "DMA0 should be disabled");
-                assert!(cpu.bus.io.has_irq(Interrupt::Dma0), "DMA0 pending irq");
+                assert!(!dma3.dma_enable(), "DMA3 should be disabled");
+                assert!(cpu.bus.io.has_irq(Interrupt::Dma3), "DMA3 pending irq");
             })
-            .run(17)
+            .run_while(|cpu| !cpu.bus.io.has_irq(Interrupt::Dma3)) // delayed, one unit per step
             .into_snapshot();
 
         assert_snapshot!(snapshot);
@@ -1,4 +1,7 @@
-use crate::{bus::types::Interrupt, utils::bitflags::Bitflag};
+use crate::{
+    bus::types::Interrupt,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
 
 #[derive(Debug)]
 pub struct Keypad {
@@ -15,6 +18,8 @@ impl Default for Keypad {
     }
 }
 
+impl_savestate!(Keypad { keyinput, keycnt });
+
 impl Keypad {
     pub fn poll_interrupt(&self) -> Option<Interrupt> {
         if !self.irq_enable() {
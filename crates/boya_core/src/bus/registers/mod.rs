@@ -4,16 +4,18 @@ use crate::{
         registers::{
             dma::{Dma, DmaChannel},
             keypad::Keypad,
+            sio::Sio,
             timer::{Timer, TimerUnit},
             waitcnt::Waitcnt,
         },
-        types::Interrupt,
+        types::{Interrupt, PowerMode},
     },
-    utils::bitflags::Bitflag,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
 };
 
 pub mod dma;
 pub mod keypad;
+pub mod sio;
 pub mod timer;
 pub mod waitcnt;
 
@@ -23,6 +25,8 @@ pub struct IORegister {
     pub dma: [Dma; 4],
     /// 0x100: Timer 0-3 Control (R/W)
     pub timer: [Timer; 4],
+    /// 0x120: Serial Communication (R/W), also 0x134 and 0x140-0x15A
+    pub sio: Sio,
     /// 0x130: Key Status (R), Key Interrupt Control (R/W)
     pub keypad: Keypad,
     /// 0x200: Interrupt Enable (R/W)
@@ -39,6 +43,8 @@ pub struct IORegister {
     pub haltcnt_h: u8,
     /// 0x800: Undocumented - Internal Memory Control (R/W)
     pub imemcnt: u32,
+
+    power_mode: PowerMode,
 }
 
 impl IORegister {
@@ -64,6 +70,7 @@ impl IORegister {
         self.timer
             .iter_mut()
             .find_map(|t| t.poll_interrupt())
+            .or_else(|| self.sio.poll_interrupt())
             .or_else(|| self.keypad.poll_interrupt())
     }
 
@@ -83,15 +90,67 @@ impl IORegister {
         self.irf.set(irq as u16);
     }
 
+    pub fn halted(&self) -> bool {
+        self.power_mode == PowerMode::Halt
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.power_mode == PowerMode::Stop
+    }
+
+    /// Leaves low power mode once an enabled interrupt is requested.
+    pub fn try_wake(&mut self) {
+        let pending = self.ie & self.irf;
+        let wake = match self.power_mode {
+            PowerMode::Running => false,
+            PowerMode::Halt => pending != 0,
+            PowerMode::Stop => [Interrupt::Serial, Interrupt::Keypad, Interrupt::Gamepak]
+                .iter()
+                .any(|&irq| pending.has(irq as u16)),
+        };
+
+        if wake {
+            self.power_mode = PowerMode::Running;
+        }
+    }
+
+    pub fn write_haltcnt(&mut self, value: u8) {
+        self.haltcnt_h = value;
+        self.power_mode = match value.has(7) {
+            true => PowerMode::Stop,
+            false => PowerMode::Halt,
+        };
+    }
+
     pub fn write_irf(&mut self, address: u32, value: u8) {
         self.irf
             .write_byte(address & 1, self.irf.read_byte(address) & !value);
     }
 }
 
-impl Bus for IORegister {
-    fn read_byte(&self, address: u32) -> u8 {
-        match address % 0x0400_0000 {
+impl_savestate!(IORegister {
+    dma,
+    timer,
+    sio,
+    keypad,
+    ie,
+    irf,
+    waitcnt,
+    ime,
+    haltcnt_l,
+    haltcnt_h,
+    imemcnt,
+    power_mode
+});
+
+impl IORegister {
+    /// Returns `None` for unused and write-only registers, which read as open bus.
+    pub fn read(&self, address: u32) -> Option<u8> {
+        let value = match address % 0x0400_0000 {
+            // DMA word counts and the unused halves of mapped words read as zero
+            0x0B8..=0x0B9 | 0x0C4..=0x0C5 | 0x0D0..=0x0D1 | 0x0DC..=0x0DD => 0,
+            0x136..=0x137 | 0x142..=0x143 | 0x15A..=0x15B => 0,
+            0x206..=0x207 | 0x20A..=0x20B => 0,
             0x0BA..=0x0BB => self.dma[0].read_byte(address),
             0x0C6..=0x0C7 => self.dma[1].read_byte(address),
             0x0D2..=0x0D3 => self.dma[2].read_byte(address),
@@ -100,6 +159,9 @@ impl Bus for IORegister {
             0x104..=0x107 => self.timer[1].read_byte(address),
             0x108..=0x10B => self.timer[2].read_byte(address),
             0x10C..=0x10F => self.timer[3].read_byte(address),
+            0x120..=0x12B | 0x134..=0x135 | 0x140..=0x141 | 0x150..=0x159 => {
+                self.sio.read_byte(address)
+            }
             0x130..=0x131 => self.keypad.keyinput.read_byte(address),
             0x132..=0x133 => self.keypad.keycnt.read_byte(address),
             0x200..=0x201 => self.ie.read_byte(address),
@@ -109,8 +171,16 @@ impl Bus for IORegister {
             0x300 => self.haltcnt_l,
             0x301 => self.haltcnt_h,
             0x800..=0x803 => self.imemcnt.read_byte(address),
-            _ => 0, // TODO: open bus
-        }
+            _ => return None,
+        };
+
+        Some(value)
+    }
+}
+
+impl Bus for IORegister {
+    fn read_byte(&self, address: u32) -> u8 {
+        self.read(address).unwrap_or_default()
     }
 
     fn write_byte(&mut self, address: u32, value: u8) {
@@ -123,13 +193,16 @@ impl Bus for IORegister {
             0x104..=0x107 => self.timer[1].write_byte(address, value),
             0x108..=0x10B => self.timer[2].write_byte(address, value),
             0x10C..=0x10F => self.timer[3].write_byte(address, value),
+            0x120..=0x12B | 0x134..=0x135 | 0x140..=0x141 | 0x150..=0x159 => {
+                self.sio.write_byte(address, value)
+            }
             0x132..=0x133 => self.keypad.keycnt.write_byte(address, value),
             0x200..=0x201 => self.ie.write_byte(address, value),
             0x202..=0x203 => self.write_irf(address, value),
             0x204..=0x205 => self.waitcnt.value.write_byte(address, value),
             0x208..=0x209 => self.ime.write_byte(address, value),
             0x300 => self.haltcnt_l = value,
-            0x301 => self.haltcnt_h = value,
+            0x301 => self.write_haltcnt(value),
             0x410..=0x411 => {} // undocumented, purpose unknown
             0x800..=0x803 => self.imemcnt.write_byte(address, value),
             _ => {}
@@ -154,7 +227,10 @@ impl IORegister {
 
 #[cfg(test)]
 mod tests {
-    use crate::{bus::types::Interrupt, test::GbaTestBuilder};
+    use crate::{
+        bus::{Bus, registers::IORegister, types::Interrupt},
+        test::GbaTestBuilder,
+    };
 
     #[test]
     fn test_irq_registers() {
@@ -181,4 +257,27 @@ mod tests {
             })
             .run(10);
     }
+
+    #[test]
+    fn test_low_power_wake() {
+        let mut io = IORegister::new();
+
+        io.write_byte(0x0400_0301, 0);
+        io.try_wake();
+        assert!(io.halted());
+
+        io.enable_irq(Interrupt::Timer0);
+        io.set_irq(Interrupt::Timer0);
+        io.try_wake();
+        assert!(!io.halted(), "any enabled IRQ ends HALT");
+
+        io.write_byte(0x0400_0301, 0x80);
+        io.try_wake();
+        assert!(io.stopped(), "timer IRQ does not end STOP");
+
+        io.enable_irq(Interrupt::Keypad);
+        io.set_irq(Interrupt::Keypad);
+        io.try_wake();
+        assert!(!io.stopped());
+    }
 }
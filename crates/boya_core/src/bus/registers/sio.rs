@@ -0,0 +1,399 @@
+use crate::{
+    bus::{
+        Bus,
+        link::{LINK_PLAYERS, LinkFrame, LinkTransport},
+        types::Interrupt,
+    },
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
+
+/// Cycles per bit at 9600, 38400, 57600 and 115200 bauds.
+const BAUD_CYCLES: [u32; 4] = [1747, 437, 291, 146];
+
+#[derive(Debug, Default)]
+pub struct Sio {
+    /// 0x120: SIODATA32 (Normal 32bit) or SIOMULTI0-3 (Multiplayer) (R/W)
+    pub multi: [u16; 4],
+    /// 0x128: SIO Control (R/W)
+    pub cnt: u16,
+    /// 0x12A: SIODATA8 (Normal 8bit, UART) or SIOMLT_SEND (Multiplayer) (R/W)
+    pub send: u16,
+    /// 0x134: SIO Mode Select / General Purpose Data (R/W)
+    pub rcnt: u16,
+    /// 0x140: JOY Bus Control (R/W)
+    pub joycnt: u16,
+    /// 0x150: JOY Bus Receive Data (R/W)
+    pub joy_recv: u32,
+    /// 0x154: JOY Bus Send Data (R/W)
+    pub joy_trans: u32,
+    /// 0x158: JOY Bus Receive Status (R/W)
+    pub joystat: u16,
+
+    /// Cycles elapsed in the transfer clocked by this end, `None` while idle.
+    elapsed: Option<u32>,
+    pending_irq: Option<Interrupt>,
+    link: Option<Box<dyn LinkTransport>>,
+}
+
+impl_savestate!(Sio {
+    multi,
+    cnt,
+    send,
+    rcnt,
+    joycnt,
+    joy_recv,
+    joy_trans,
+    joystat,
+    elapsed,
+    pending_irq
+});
+
+impl Sio {
+    pub fn connect(&mut self, link: Box<dyn LinkTransport>) {
+        self.link = Some(link);
+        self.publish();
+    }
+
+    pub fn disconnect(&mut self) -> Option<Box<dyn LinkTransport>> {
+        self.link.take()
+    }
+
+    pub fn connected(&self) -> bool {
+        self.link.is_some()
+    }
+
+    /// Position on the cable, a console without cable is its own master.
+    pub fn player(&self) -> usize {
+        self.link.as_ref().map_or(0, |link| link.player())
+    }
+
+    pub fn mode(&self) -> SioMode {
+        match (self.rcnt.get_bits(14, 15), self.cnt.get_bits(12, 13)) {
+            (0 | 1, 0) => SioMode::Normal8,
+            (0 | 1, 1) => SioMode::Normal32,
+            (0 | 1, 2) => SioMode::Multiplayer,
+            (0 | 1, _) => SioMode::Uart,
+            (2, _) => SioMode::GeneralPurpose,
+            _ => SioMode::JoyBus,
+        }
+    }
+
+    pub fn internal_clock(&self) -> bool {
+        self.cnt.has(0)
+    }
+
+    pub fn busy(&self) -> bool {
+        self.cnt.has(7)
+    }
+
+    pub fn irq_enable(&self) -> bool {
+        self.cnt.has(14)
+    }
+
+    pub fn poll_interrupt(&mut self) -> Option<Interrupt> {
+        self.pending_irq.take()
+    }
+
+    pub fn tick(&mut self, cycles: u32) {
+        // started here rather than on write, SIOCNT is written one byte at a time
+        if self.elapsed.is_none() && self.clocks_transfer() {
+            self.elapsed = Some(0);
+        }
+
+        if let Some(elapsed) = self.elapsed {
+            let elapsed = elapsed + cycles;
+
+            match elapsed >= self.transfer_cycles() {
+                true => self.finish_transfer(),
+                false => self.elapsed = Some(elapsed),
+            }
+        }
+
+        // data clocked in while this end isn't ready is lost
+        let listening = self.listening();
+
+        while let Some(frame) = self.link.as_mut().and_then(|link| link.poll()) {
+            if listening {
+                self.receive(frame);
+                break;
+            }
+        }
+    }
+
+    /// Data shifted out by this end in the current mode.
+    fn send_data(&self) -> u32 {
+        match self.mode() {
+            SioMode::Normal8 | SioMode::Uart => self.send as u32 & 0xFF,
+            SioMode::Normal32 => self.multi[0] as u32 | (self.multi[1] as u32) << 16,
+            SioMode::Multiplayer => self.send as u32,
+            _ => 0,
+        }
+    }
+
+    fn publish(&mut self) {
+        let data = self.send_data();
+
+        if let Some(link) = &mut self.link {
+            link.publish(data);
+        }
+    }
+
+    /// Whether a transfer was started and this end provides its clock.
+    fn clocks_transfer(&self) -> bool {
+        match self.mode() {
+            SioMode::Normal8 | SioMode::Normal32 => self.busy() && self.internal_clock(),
+            SioMode::Multiplayer => self.busy() && self.player() == 0,
+            _ => false,
+        }
+    }
+
+    /// Whether a transfer started by another player would be received.
+    fn listening(&self) -> bool {
+        match self.mode() {
+            SioMode::Normal8 | SioMode::Normal32 => self.busy() && !self.internal_clock(),
+            SioMode::Multiplayer => self.player() != 0,
+            SioMode::Uart => self.cnt.has(11),
+            _ => false,
+        }
+    }
+
+    fn transfer_cycles(&self) -> u32 {
+        let baud = BAUD_CYCLES[self.cnt.get_bits(0, 1) as usize];
+
+        match self.mode() {
+            SioMode::Normal8 if self.cnt.has(1) => 8 * 8,
+            SioMode::Normal8 => 8 * 64,
+            SioMode::Normal32 if self.cnt.has(1) => 32 * 8,
+            SioMode::Normal32 => 32 * 64,
+            // start + 16 data + stop bits for every player
+            SioMode::Multiplayer => baud * 18 * LINK_PLAYERS as u32,
+            // start + 8 data + stop bits
+            _ => baud * 10,
+        }
+    }
+
+    fn finish_transfer(&mut self) {
+        let mode = self.mode();
+        let data = self.send_data();
+
+        self.elapsed = None;
+
+        let frame = match &mut self.link {
+            Some(link) => link.transfer(mode, data),
+            None => LinkFrame {
+                mode,
+                data: [Some(data), None, None, None],
+            },
+        };
+
+        match mode {
+            SioMode::Uart => self.complete(),
+            _ => self.receive(frame),
+        }
+    }
+
+    fn receive(&mut self, frame: LinkFrame) {
+        if frame.mode != self.mode() {
+            return;
+        }
+
+        let player = self.player();
+        let other = frame.other(player);
+
+        match frame.mode {
+            SioMode::Normal8 => self.send.set_bits(0, 7, other as u16 & 0xFF),
+            SioMode::Normal32 => {
+                self.multi[0] = other as u16;
+                self.multi[1] = (other >> 16) as u16;
+            }
+            SioMode::Multiplayer => {
+                for (multi, data) in self.multi.iter_mut().zip(frame.data) {
+                    *multi = data.map_or(0xFFFF, |data| data as u16);
+                }
+
+                self.cnt.set_bits(4, 5, player as u16);
+            }
+            SioMode::Uart => {
+                self.send.set_bits(0, 7, other as u16 & 0xFF);
+                self.cnt.clear(5); // receive data available
+            }
+            _ => return,
+        }
+
+        self.complete();
+    }
+
+    fn complete(&mut self) {
+        if self.mode() != SioMode::Uart {
+            self.cnt.clear(7);
+        }
+
+        if self.irq_enable() {
+            self.pending_irq = Some(Interrupt::Serial);
+        }
+    }
+
+    fn read_cnt(&self) -> u16 {
+        let mut cnt = self.cnt;
+
+        if self.mode() == SioMode::Multiplayer {
+            cnt.update(2, self.player() != 0); // SI terminal, low for the parent
+            cnt.update(3, self.connected()); // SD terminal, high when all consoles are ready
+        }
+
+        cnt
+    }
+
+    fn write_cnt(&mut self, address: u32, value: u8) {
+        let status = self.cnt & 0x0070;
+
+        self.cnt.write_byte(address, value);
+
+        if self.mode() == SioMode::Multiplayer {
+            self.cnt = (self.cnt & !0x0070) | status; // player id and error are read-only
+        }
+    }
+}
+
+impl Bus for Sio {
+    fn read_byte(&self, address: u32) -> u8 {
+        match address % 0x0400_0000 {
+            addr @ 0x120..=0x127 => self.multi[(addr as usize - 0x120) / 2].read_byte(address),
+            0x128..=0x129 => self.read_cnt().read_byte(address),
+            0x12A..=0x12B => self.send.read_byte(address),
+            0x134..=0x135 => self.rcnt.read_byte(address),
+            0x140..=0x141 => self.joycnt.read_byte(address),
+            0x150..=0x153 => self.joy_recv.read_byte(address),
+            0x154..=0x157 => self.joy_trans.read_byte(address),
+            0x158..=0x159 => self.joystat.read_byte(address),
+            _ => 0,
+        }
+    }
+
+    fn write_byte(&mut self, address: u32, value: u8) {
+        match address % 0x0400_0000 {
+            addr @ 0x120..=0x127 => {
+                self.multi[(addr as usize - 0x120) / 2].write_byte(address, value)
+            }
+            0x128..=0x129 => self.write_cnt(address, value),
+            0x12A..=0x12B => {
+                self.send.write_byte(address, value);
+
+                if self.mode() == SioMode::Uart && self.cnt.has(10) && address & 1 == 0 {
+                    self.cnt.set(5); // receive data consumed
+                    self.elapsed = Some(0);
+                }
+            }
+            0x134..=0x135 => self.rcnt.write_byte(address, value),
+            0x140..=0x141 => self.joycnt.write_byte(address, value),
+            0x150..=0x153 => self.joy_recv.write_byte(address, value),
+            0x154..=0x157 => self.joy_trans.write_byte(address, value),
+            0x158..=0x159 => self.joystat.write_byte(address, value),
+            _ => {}
+        }
+
+        self.publish();
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SioMode {
+    #[default]
+    Normal8,
+    Normal32,
+    Multiplayer,
+    Uart,
+    GeneralPurpose,
+    JoyBus,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::{
+        Bus,
+        link::{LocalLink, Loopback},
+        registers::sio::Sio,
+        types::Interrupt,
+    };
+
+    #[test]
+    fn test_normal_loopback() {
+        let mut sio = Sio::default();
+
+        sio.connect(Box::new(Loopback));
+        sio.write_word(0x0400_0120, 0x1234_5678);
+        sio.write_hword(0x0400_0128, 0x5083); // 32bit, 2MHz internal clock, IRQ, start
+
+        sio.tick(32 * 8 - 1);
+        assert!(sio.busy());
+
+        sio.tick(1);
+        assert!(!sio.busy());
+        assert_eq!(sio.read_word(0x0400_0120), 0x1234_5678);
+        assert!(matches!(sio.poll_interrupt(), Some(Interrupt::Serial)));
+    }
+
+    #[test]
+    fn test_multiplayer_link() {
+        let mut consoles = LocalLink::cable(2)
+            .into_iter()
+            .enumerate()
+            .map(|(player, link)| {
+                let mut sio = Sio::default();
+
+                sio.connect(Box::new(link));
+                sio.write_hword(0x0400_0134, 0);
+                sio.write_hword(0x0400_0128, 0x6003); // multiplayer, 115200 bauds, IRQ
+                sio.write_hword(0x0400_012A, 0xA0 + player as u16);
+                sio
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            consoles[1].read_hword(0x0400_0128) & 0b1100,
+            0b1100,
+            "child"
+        );
+
+        consoles[1].write_hword(0x0400_0128, 0x6083);
+        consoles[1].tick(0x10000);
+        assert_eq!(consoles[1].elapsed, None, "children can't start");
+
+        consoles[0].write_hword(0x0400_0128, 0x6083);
+        consoles.iter_mut().for_each(|sio| sio.tick(0x10000));
+
+        for (player, sio) in consoles.iter_mut().enumerate() {
+            let multi = [0, 2, 4, 6].map(|offset| sio.read_hword(0x0400_0120 + offset));
+
+            assert_eq!(multi, [0xA0, 0xA1, 0xFFFF, 0xFFFF]);
+            assert_eq!(sio.read_hword(0x0400_0128) >> 4 & 3, player as u16);
+            assert!(matches!(sio.poll_interrupt(), Some(Interrupt::Serial)));
+        }
+    }
+
+    #[test]
+    fn test_normal_slave_not_ready() {
+        let mut consoles = LocalLink::cable(2).into_iter().map(|link| {
+            let mut sio = Sio::default();
+
+            sio.connect(Box::new(link));
+            sio
+        });
+        let (mut master, mut slave) = (consoles.next().unwrap(), consoles.next().unwrap());
+
+        master.write_hword(0x0400_0128, 0x5083); // 32bit, 2MHz internal clock, IRQ, start
+        master.tick(32 * 8);
+        assert!(!master.busy());
+
+        slave.write_hword(0x0400_0128, 0x5000); // external clock, not started yet
+        slave.tick(1);
+
+        slave.write_hword(0x0400_0128, 0x5080);
+        slave.tick(0x10000);
+        assert!(
+            slave.busy(),
+            "transfer clocked before the start bit is lost"
+        );
+        assert!(slave.poll_interrupt().is_none());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     bus::{Bus, types::Interrupt},
-    utils::bitflags::Bitflag,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
 };
 
 #[derive(Debug, Default)]
@@ -133,6 +133,15 @@ impl From<TimerUnit> for Interrupt {
     }
 }
 
+impl_savestate!(Timer {
+    cnt_l,
+    cnt_h,
+    counter,
+    divider,
+    overflow,
+    pending_irq
+});
+
 #[cfg(test)]
 mod tests {
     use crate::{bus::types::Interrupt, test::GbaTestBuilder};
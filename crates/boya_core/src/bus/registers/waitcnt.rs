@@ -1,10 +1,15 @@
-use crate::{bus::types::WaitState, utils::bitflags::Bitflag};
+use crate::{
+    bus::types::WaitState,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
 
 #[derive(Debug, Default)]
 pub struct Waitcnt {
     pub value: u16,
 }
 
+impl_savestate!(Waitcnt { value });
+
 impl Waitcnt {
     pub fn sram_wait(&self) -> WaitState {
         let wait = self.two_bits_wait(0, 1);
@@ -1,5 +1,9 @@
 use std::ops::{Add, AddAssign};
 
+use crate::utils::savestate::{
+    Savestate, StateError, StateReader, StateWriter, impl_savestate_enum,
+};
+
 #[derive(Debug, Clone, Copy)]
 pub enum DataType {
     Byte = 1,
@@ -13,7 +17,7 @@ impl DataType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum MemoryAccess {
     Seq,
     NonSeq,
@@ -132,6 +136,68 @@ pub enum Interrupt {
     Gamepak,
 }
 
+impl_savestate_enum!(Interrupt {
+    VBlank,
+    HBlank,
+    VCount,
+    Timer0,
+    Timer1,
+    Timer2,
+    Timer3,
+    Serial,
+    Dma0,
+    Dma1,
+    Dma2,
+    Dma3,
+    Keypad,
+    Gamepak
+});
+
+/// Same layout as `Option<T>`, without requiring a default interrupt.
+impl Savestate for Option<Interrupt> {
+    fn save_state(&self, writer: &mut StateWriter) {
+        self.is_some().save_state(writer);
+
+        if let Some(irq) = self {
+            irq.save_state(writer);
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        let mut some = false;
+
+        some.load_state(reader)?;
+
+        *self = match some {
+            true => {
+                let mut irq = Interrupt::VBlank;
+                irq.load_state(reader)?;
+                Some(irq)
+            }
+            false => None,
+        };
+
+        Ok(())
+    }
+}
+
+/// Low power state entered by writing to HALTCNT.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum PowerMode {
+    #[default]
+    Running,
+    /// CPU paused until `IE & IF` becomes non-zero.
+    Halt,
+    /// Whole system paused until a keypad, serial or gamepak interrupt.
+    Stop,
+}
+
+impl_savestate_enum!(PowerMode {
+    Running,
+    Halt,
+    Stop
+});
+
 #[derive(Debug)]
 pub struct InterruptResult {
     pub cycles: Cycle,
@@ -23,6 +23,6 @@ impl From<u32> for Instruction {
 
 impl Executable for Instruction {
     fn dispatch(self, cpu: &mut Arm7tdmi) -> Cycle {
-        cpu.swi()
+        cpu.swi(self.nn.get_bits_u8(16, 23))
     }
 }
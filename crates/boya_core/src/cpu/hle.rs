@@ -0,0 +1,631 @@
+use std::f32::consts::PI;
+
+use crate::{
+    bus::{
+        Bus,
+        types::{Cycle, MemoryAccess},
+    },
+    cpu::{Arm7tdmi, psr::Psr, register::Register},
+};
+
+/// Interrupt flags acknowledged by the user IRQ handler, polled by `IntrWait`.
+pub const BIOS_IF: u32 = 0x0300_7FF8;
+pub const BIOS_CHECKSUM: u32 = 0xBAAE_187F;
+
+/// Areas cleared by `RegisterRamReset`, indexed by their flag bit.
+const RAM_RESET_AREAS: [(u8, u32, u32); 10] = [
+    (0, 0x0200_0000, 0x0204_0000), // EWRAM
+    (1, 0x0300_0000, 0x0300_7E00), // IWRAM, without the stacks
+    (2, 0x0500_0000, 0x0500_0400), // palette
+    (3, 0x0600_0000, 0x0601_8000), // VRAM
+    (4, 0x0700_0000, 0x0700_0400), // OAM
+    (5, 0x0400_0120, 0x0400_0130), // SIO
+    (6, 0x0400_0060, 0x0400_00B0), // sound
+    (7, 0x0400_0000, 0x0400_0060), // LCD
+    (7, 0x0400_00B0, 0x0400_0120), // DMA and timers
+    (7, 0x0400_0200, 0x0400_020C), // interrupts and WAITCNT
+];
+
+/// Minimal BIOS image used in HLE mode, it only contains the IRQ dispatcher.
+///
+/// 0x018: B       0x128
+/// 0x128: STMFD   SP!, {R0-R3, R12, LR}
+///        MOV     R0, #0x0400_0000
+///        ADD     LR, PC, #0
+///        LDR     PC, [R0, #-4]
+///        LDMFD   SP!, {R0-R3, R12, LR}
+///        SUBS    PC, LR, #4
+pub const HLE_BIOS: [(u32, u32); 7] = [
+    (0x018, 0xEA00_0042),
+    (0x128, 0xE92D_500F),
+    (0x12C, 0xE3A0_0301),
+    (0x130, 0xE28F_E000),
+    (0x134, 0xE510_F004),
+    (0x138, 0xE8BD_500F),
+    (0x13C, 0xE25E_F004),
+];
+
+/// Native implementation of the BIOS calls, timing is not emulated.
+impl Arm7tdmi {
+    pub fn hle_swi(&mut self, comment: u8) -> Cycle {
+        match comment {
+            0x00 => self.soft_reset(),
+            0x01 => self.register_ram_reset(),
+            0x02 => self.bus.io.write_haltcnt(0x00),
+            0x03 => self.bus.io.write_haltcnt(0x80),
+            0x04 => self.intr_wait(self.reg(0) != 0, self.reg(1) as u16),
+            0x05 => self.intr_wait(true, 1),
+            0x06 => self.div(self.reg(0) as i32, self.reg(1) as i32),
+            0x07 => self.div(self.reg(1) as i32, self.reg(0) as i32),
+            0x08 => self.set_reg(0, self.reg(0).isqrt()),
+            0x09 => self.set_reg(0, arctan(self.reg(0) as i16 as i32) as u16 as u32),
+            0x0A => self.set_reg(
+                0,
+                arctan2(self.reg(0) as i16 as i32, self.reg(1) as i16 as i32),
+            ),
+            0x0B => self.cpu_set(),
+            0x0C => self.cpu_fast_set(),
+            0x0D => self.set_reg(0, BIOS_CHECKSUM),
+            0x0E => self.bg_affine_set(),
+            0x0F => self.obj_affine_set(),
+            0x10 => self.bit_unpack(),
+            0x11 => self.lz77_uncomp(false),
+            0x12 => self.lz77_uncomp(true),
+            0x13 => self.huff_uncomp(),
+            0x14 => self.rl_uncomp(false),
+            0x15 => self.rl_uncomp(true),
+            0x16 => self.diff_unfilter(false, false),
+            0x17 => self.diff_unfilter(false, true),
+            0x18 => self.diff_unfilter(true, true),
+            // the sound driver, multiboot and hard reset run BIOS code that HLE mode doesn't have
+            _ => {}
+        }
+
+        self.pre_fetch_cycle(MemoryAccess::Seq)
+    }
+
+    fn reg(&self, index: u8) -> u32 {
+        self.registers.get(index, self.operating_mode())
+    }
+
+    fn set_reg(&mut self, index: u8, value: u32) {
+        self.registers.set(index, value, self.operating_mode());
+    }
+
+    /// Halts until one of `flags` is acknowledged in `BIOS_IF`, the SWI is re-executed after each
+    /// wake up.
+    fn intr_wait(&mut self, discard: bool, flags: u16) {
+        let discard = discard && !std::mem::replace(&mut self.hle_intr_wait, false);
+        let acknowledged = self.bus.read_hword(BIOS_IF);
+
+        if discard {
+            self.bus.write_hword(BIOS_IF, acknowledged & !flags);
+        } else if acknowledged & flags != 0 {
+            self.bus.write_hword(BIOS_IF, acknowledged & !flags);
+            return;
+        }
+
+        self.hle_intr_wait = true;
+        self.bus.io.ime = 1;
+        self.bus.io.write_haltcnt(0x00);
+        self.registers.set_pc(self.exec_address());
+    }
+
+    /// Restarts the cartridge, or the multiboot image when the byte at 0x0300_7FFA is set.
+    fn soft_reset(&mut self) {
+        let entry = match self.bus.read_byte(0x0300_7FFA) {
+            0 => 0x0800_0000,
+            _ => 0x0200_0000,
+        };
+
+        for address in (0x0300_7E00..0x0300_8000).step_by(4) {
+            self.bus.write_word(address, 0);
+        }
+
+        self.registers = Register::default();
+        self.registers.cpsr = Psr::from(0x1F);
+        self.registers.main[13] = 0x0300_7F00;
+        self.registers.irq[0] = 0x0300_7FA0;
+        self.registers.svc[0] = 0x0300_7FE0;
+        self.registers.set_pc(entry);
+    }
+
+    fn register_ram_reset(&mut self) {
+        let flags = self.reg(0);
+
+        for (bit, start, end) in RAM_RESET_AREAS {
+            if flags & (1 << bit) != 0 {
+                (start..end)
+                    .step_by(4)
+                    .for_each(|address| self.bus.write_word(address, 0));
+            }
+        }
+
+        if flags & (1 << 5) != 0 {
+            self.bus.write_hword(0x0400_0134, 0x8000); // RCNT, general purpose mode
+        }
+
+        self.bus.write_hword(0x0400_0000, 0x0080); // forced blank
+    }
+
+    fn div(&mut self, num: i32, den: i32) {
+        let (quot, rem) = match den {
+            0 => (if num < 0 { -1 } else { 1 }, num),
+            _ => (num.wrapping_div(den), num.wrapping_rem(den)),
+        };
+
+        self.set_reg(0, quot as u32);
+        self.set_reg(1, rem as u32);
+        self.set_reg(3, quot.unsigned_abs());
+    }
+
+    fn cpu_set(&mut self) {
+        let (mut src, mut dst, cnt) = (self.reg(0), self.reg(1), self.reg(2));
+        let count = cnt & 0x1F_FFFF;
+        let fill = cnt & (1 << 24) != 0;
+
+        if cnt & (1 << 26) != 0 {
+            let (src_step, dst_step) = (if fill { 0 } else { 4 }, 4);
+            src &= !3;
+            dst &= !3;
+
+            for _ in 0..count {
+                let value = self.bus.read_word(src);
+                self.bus.write_word(dst, value);
+                src = src.wrapping_add(src_step);
+                dst = dst.wrapping_add(dst_step);
+            }
+        } else {
+            let (src_step, dst_step) = (if fill { 0 } else { 2 }, 2);
+            src &= !1;
+            dst &= !1;
+
+            for _ in 0..count {
+                let value = self.bus.read_hword(src);
+                self.bus.write_hword(dst, value);
+                src = src.wrapping_add(src_step);
+                dst = dst.wrapping_add(dst_step);
+            }
+        }
+    }
+
+    fn cpu_fast_set(&mut self) {
+        let (mut src, mut dst, cnt) = (self.reg(0) & !3, self.reg(1) & !3, self.reg(2));
+        let count = (cnt & 0x1F_FFFF).next_multiple_of(8);
+        let fill = cnt & (1 << 24) != 0;
+        let value = self.bus.read_word(src);
+
+        for _ in 0..count {
+            let value = if fill { value } else { self.bus.read_word(src) };
+
+            self.bus.write_word(dst, value);
+            src = src.wrapping_add(4);
+            dst = dst.wrapping_add(4);
+        }
+    }
+
+    fn bg_affine_set(&mut self) {
+        let (mut src, mut dst) = (self.reg(0), self.reg(1));
+
+        for _ in 0..self.reg(2) {
+            let ox = self.bus.read_word(src) as i32 as f32 / 256.0;
+            let oy = self.bus.read_word(src.wrapping_add(4)) as i32 as f32 / 256.0;
+            let cx = self.bus.read_hword(src.wrapping_add(8)) as i16 as f32;
+            let cy = self.bus.read_hword(src.wrapping_add(10)) as i16 as f32;
+            let sx = self.bus.read_hword(src.wrapping_add(12)) as i16 as f32 / 256.0;
+            let sy = self.bus.read_hword(src.wrapping_add(14)) as i16 as f32 / 256.0;
+            let [pa, pb, pc, pd] = rotscale(sx, sy, self.bus.read_hword(src.wrapping_add(16)));
+            let x = ox - (pa * cx + pb * cy);
+            let y = oy - (pc * cx + pd * cy);
+
+            for (i, param) in [pa, pb, pc, pd].into_iter().enumerate() {
+                self.bus.write_hword(
+                    dst.wrapping_add(i as u32 * 2),
+                    (param * 256.0) as i16 as u16,
+                );
+            }
+
+            self.bus
+                .write_word(dst.wrapping_add(8), (x * 256.0) as i32 as u32);
+            self.bus
+                .write_word(dst.wrapping_add(12), (y * 256.0) as i32 as u32);
+            src = src.wrapping_add(20);
+            dst = dst.wrapping_add(16);
+        }
+    }
+
+    fn obj_affine_set(&mut self) {
+        let (mut src, mut dst, stride) = (self.reg(0), self.reg(1), self.reg(3));
+
+        for _ in 0..self.reg(2) {
+            let sx = self.bus.read_hword(src) as i16 as f32 / 256.0;
+            let sy = self.bus.read_hword(src.wrapping_add(2)) as i16 as f32 / 256.0;
+            let params = rotscale(sx, sy, self.bus.read_hword(src.wrapping_add(4)));
+
+            for param in params {
+                self.bus.write_hword(dst, (param * 256.0) as i16 as u16);
+                dst = dst.wrapping_add(stride);
+            }
+
+            src = src.wrapping_add(8);
+        }
+    }
+
+    fn bit_unpack(&mut self) {
+        let (mut src, mut dst, info) = (self.reg(0), self.reg(1) & !3, self.reg(2));
+        let len = self.bus.read_hword(info);
+        let src_bits = self.bus.read_byte(info.wrapping_add(2)) as u32;
+        let dst_bits = self.bus.read_byte(info.wrapping_add(3)) as u32;
+        let offset = self.bus.read_word(info.wrapping_add(4));
+        let offset_zero = offset & (1 << 31) != 0;
+
+        if !matches!(src_bits, 1 | 2 | 4 | 8) || !matches!(dst_bits, 1 | 2 | 4 | 8 | 16 | 32) {
+            return;
+        }
+
+        let mut block = 0u32;
+        let mut block_bits = 0;
+
+        for _ in 0..len {
+            let byte = self.bus.read_byte(src) as u32;
+            src = src.wrapping_add(1);
+
+            for shift in (0..8).step_by(src_bits as usize) {
+                let mut unit = (byte >> shift) & ((1 << src_bits) - 1);
+
+                if unit != 0 || offset_zero {
+                    unit = unit.wrapping_add(offset & 0x7FFF_FFFF);
+                }
+
+                block |= (unit & (u32::MAX >> (32 - dst_bits))) << block_bits;
+                block_bits += dst_bits;
+
+                if block_bits == 32 {
+                    self.bus.write_word(dst, block);
+                    dst = dst.wrapping_add(4);
+                    block = 0;
+                    block_bits = 0;
+                }
+            }
+        }
+    }
+
+    fn lz77_uncomp(&mut self, vram: bool) {
+        let mut src = self.reg(0) & !3;
+        let size = (self.bus.read_word(src) >> 8) as usize;
+        let mut output = Vec::with_capacity(size);
+
+        src = src.wrapping_add(4);
+
+        while output.len() < size {
+            let flags = self.bus.read_byte(src);
+            src = src.wrapping_add(1);
+
+            for bit in (0..8).rev() {
+                if output.len() >= size {
+                    break;
+                }
+
+                if flags & (1 << bit) == 0 {
+                    output.push(self.bus.read_byte(src));
+                    src = src.wrapping_add(1);
+                    continue;
+                }
+
+                let [b0, b1] = [
+                    self.bus.read_byte(src),
+                    self.bus.read_byte(src.wrapping_add(1)),
+                ];
+                let disp = (((b0 as usize & 0xF) << 8) | b1 as usize) + 1;
+                let len = (b0 as usize >> 4) + 3;
+
+                src = src.wrapping_add(2);
+
+                for _ in 0..len {
+                    let byte = output.len().checked_sub(disp).map_or(0, |i| output[i]);
+                    output.push(byte);
+                }
+            }
+        }
+
+        output.truncate(size);
+        self.write_output(&output, vram);
+    }
+
+    fn huff_uncomp(&mut self) {
+        let src = self.reg(0) & !3;
+        let mut dst = self.reg(1);
+        let header = self.bus.read_word(src);
+        let bits = match header & 0xF {
+            0 => 8,
+            bits => bits,
+        };
+
+        if 32 % bits != 0 || bits == 1 {
+            return;
+        }
+
+        let mut remaining = (header >> 8) as i32;
+        let tree = src.wrapping_add(5);
+        let tree_size = self.bus.read_byte(src.wrapping_add(4)) as u32 * 2 + 1;
+        let mut stream = tree.wrapping_add(tree_size);
+        let mut node_addr = tree;
+        let mut block = 0u32;
+        let mut block_bits = 0;
+
+        while remaining > 0 {
+            let word = self.bus.read_word(stream);
+            stream = stream.wrapping_add(4);
+
+            for bit in (0..32).rev() {
+                if remaining <= 0 {
+                    break;
+                }
+
+                let node = self.bus.read_byte(node_addr);
+                let next = (node_addr & !1).wrapping_add((node as u32 & 0x3F) * 2 + 2);
+                let (child, is_data) = match word & (1 << bit) != 0 {
+                    true => (next.wrapping_add(1), node & 0x40 != 0),
+                    false => (next, node & 0x80 != 0),
+                };
+
+                if !is_data {
+                    node_addr = child;
+                    continue;
+                }
+
+                block |= (self.bus.read_byte(child) as u32 & ((1 << bits) - 1)) << block_bits;
+                block_bits += bits;
+                node_addr = tree;
+
+                if block_bits == 32 {
+                    self.bus.write_word(dst, block);
+                    dst = dst.wrapping_add(4);
+                    remaining -= 4;
+                    block = 0;
+                    block_bits = 0;
+                }
+            }
+        }
+    }
+
+    fn rl_uncomp(&mut self, vram: bool) {
+        let mut src = self.reg(0) & !3;
+        let size = (self.bus.read_word(src) >> 8) as usize;
+        let mut output = Vec::with_capacity(size);
+
+        src = src.wrapping_add(4);
+
+        while output.len() < size {
+            let flag = self.bus.read_byte(src);
+            src = src.wrapping_add(1);
+
+            if flag & 0x80 != 0 {
+                let byte = self.bus.read_byte(src);
+                src = src.wrapping_add(1);
+                output.extend(std::iter::repeat_n(byte, (flag as usize & 0x7F) + 3));
+            } else {
+                for _ in 0..(flag & 0x7F) + 1 {
+                    output.push(self.bus.read_byte(src));
+                    src = src.wrapping_add(1);
+                }
+            }
+        }
+
+        output.truncate(size);
+        self.write_output(&output, vram);
+    }
+
+    fn diff_unfilter(&mut self, hword: bool, vram: bool) {
+        let mut src = self.reg(0) & !3;
+        let size = (self.bus.read_word(src) >> 8) as usize;
+        let mut output = Vec::with_capacity(size);
+
+        src = src.wrapping_add(4);
+
+        if hword {
+            let mut value = 0u16;
+
+            while output.len() < size {
+                value = value.wrapping_add(self.bus.read_hword(src));
+                src = src.wrapping_add(2);
+                output.extend(value.to_le_bytes());
+            }
+        } else {
+            let mut value = 0u8;
+
+            while output.len() < size {
+                value = value.wrapping_add(self.bus.read_byte(src));
+                src = src.wrapping_add(1);
+                output.push(value);
+            }
+        }
+
+        output.truncate(size);
+        self.write_output(&output, vram);
+    }
+
+    /// VRAM does not support 8-bit writes, so the output is stored by halfwords instead.
+    fn write_output(&mut self, output: &[u8], vram: bool) {
+        let dst = self.reg(1);
+
+        if vram {
+            for (i, chunk) in output.chunks(2).enumerate() {
+                let value = u16::from_le_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]);
+                self.bus
+                    .write_hword((dst & !1).wrapping_add(i as u32 * 2), value);
+            }
+        } else {
+            for (i, &byte) in output.iter().enumerate() {
+                self.bus.write_byte(dst.wrapping_add(i as u32), byte);
+            }
+        }
+    }
+}
+
+/// Returns the `pa`, `pb`, `pc` and `pd` parameters, `theta` is a 0-0xFFFF angle with 8 bits of
+/// precision.
+fn rotscale(sx: f32, sy: f32, theta: u16) -> [f32; 4] {
+    let theta = (theta >> 8) as f32 / 128.0 * PI;
+    let (sin, cos) = theta.sin_cos();
+
+    [cos * sx, -sin * sx, sin * sy, cos * sy]
+}
+
+/// Polynomial approximation used by the BIOS, `tan` is a 1.14 fixed point value.
+fn arctan(tan: i32) -> i32 {
+    let a = -(tan.wrapping_mul(tan) >> 14);
+    let b = [0x390, 0x91C, 0xFB6, 0x16AA, 0x2081, 0x3651, 0xA2F9]
+        .into_iter()
+        .fold(0xA9, |b: i32, c| (b.wrapping_mul(a) >> 14) + c);
+
+    tan.wrapping_mul(b) >> 16
+}
+
+fn arctan2(x: i32, y: i32) -> u32 {
+    let angle = match (x, y) {
+        (x, 0) if x >= 0 => 0,
+        (_, 0) => 0x8000,
+        (0, y) if y >= 0 => 0x4000,
+        (0, _) => 0xC000,
+        (x, y) if y >= 0 && x >= 0 && x >= y => arctan((y << 14) / x),
+        (x, y) if y >= 0 && x < 0 && -x >= y => arctan((y << 14) / x) + 0x8000,
+        (x, y) if y >= 0 => 0x4000 - arctan((x << 14) / y),
+        (x, y) if x <= 0 && -x > -y => arctan((y << 14) / x) + 0x8000,
+        (x, y) if x > 0 && x >= -y => arctan((y << 14) / x) + 0x10000,
+        (x, y) => 0xC000 - arctan((x << 14) / y),
+    };
+
+    angle as u16 as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bus::Bus,
+        cpu::{
+            Arm7tdmi,
+            hle::{arctan2, rotscale},
+        },
+    };
+
+    fn swi(cpu: &mut Arm7tdmi, comment: u8, args: [u32; 4]) {
+        for (i, arg) in args.into_iter().enumerate() {
+            cpu.set_reg(i as u8, arg);
+        }
+
+        cpu.hle_swi(comment);
+    }
+
+    #[test]
+    fn test_hle_math() {
+        let mut cpu = Arm7tdmi::default();
+
+        swi(&mut cpu, 0x06, [(-7i32) as u32, 2, 0, 0]);
+        assert_eq!(
+            [cpu.reg(0) as i32, cpu.reg(1) as i32, cpu.reg(3) as i32],
+            [-3, -1, 3]
+        );
+
+        swi(&mut cpu, 0x08, [1 << 20, 0, 0, 0]);
+        assert_eq!(cpu.reg(0), 1 << 10);
+
+        assert_eq!(arctan2(0x100, 0), 0);
+        assert_eq!(arctan2(0, 0x100), 0x4000);
+        assert_eq!(arctan2(-0x100, 0), 0x8000);
+        assert!(arctan2(0x100, 0x100).abs_diff(0x2000) < 0x10, "45 degrees");
+
+        let [pa, pb, pc, pd] = rotscale(1.0, 1.0, 0x4000);
+        assert!(pa.abs() < 1e-6 && pd.abs() < 1e-6);
+        assert_eq!([pb, pc], [-1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_hle_decompression() {
+        let mut cpu = Arm7tdmi::default();
+        let src = 0x0200_0000;
+        let dst = 0x0300_0000;
+
+        // LZ77: "ABC" + copy 6 bytes from 3 bytes back
+        let lz77 = [0x10, 9, 0, 0, 0b0001_0000, b'A', b'B', b'C', 0x30, 0x02];
+        lz77.iter()
+            .enumerate()
+            .for_each(|(i, &b)| cpu.bus.write_byte(src + i as u32, b));
+
+        swi(&mut cpu, 0x11, [src, dst, 0, 0]);
+        assert_eq!(
+            (0..9)
+                .map(|i| cpu.bus.read_byte(dst + i))
+                .collect::<Vec<_>>(),
+            b"ABCABCABC"
+        );
+
+        // RL: 4 x 'Z' + literal "xy"
+        let rl = [0x30, 6, 0, 0, 0x81, b'Z', 0x01, b'x', b'y'];
+        rl.iter()
+            .enumerate()
+            .for_each(|(i, &b)| cpu.bus.write_byte(src + i as u32, b));
+
+        swi(&mut cpu, 0x15, [src, dst, 0, 0]);
+        assert_eq!(
+            (0..6)
+                .map(|i| cpu.bus.read_byte(dst + i))
+                .collect::<Vec<_>>(),
+            b"ZZZZxy"
+        );
+
+        // Huffman, 8-bit: root -> (0: 0x11, 1: 0x22), bitstream 0101...
+        let huff = [0x28, 4, 0, 0, 1, 0xC0, 0x11, 0x22];
+        huff.iter()
+            .enumerate()
+            .for_each(|(i, &b)| cpu.bus.write_byte(src + i as u32, b));
+        cpu.bus.write_word(src + 8, 0x5000_0000);
+
+        swi(&mut cpu, 0x13, [src, dst, 0, 0]);
+        assert_eq!(cpu.bus.read_word(dst), 0x2211_2211);
+    }
+
+    #[test]
+    fn test_hle_memory() {
+        let mut cpu = Arm7tdmi::default();
+        let src = 0x0200_0000;
+        let dst = 0x0300_0000;
+        let info = 0x0200_0100;
+
+        // BitUnPack: 1 bit to 4 bits, with an offset of 2 for non-zero units
+        cpu.bus.write_byte(src, 0b1010_0101);
+        cpu.bus.write_hword(info, 1);
+        cpu.bus.write_hword(info + 2, 0x0401);
+        cpu.bus.write_word(info + 4, 2);
+
+        swi(&mut cpu, 0x10, [src, dst, info, 0]);
+        assert_eq!(cpu.bus.read_word(dst), 0x3030_0303);
+
+        // Diff8bitUnFilter: 1, +2, +3
+        cpu.bus.write_word(src, 0x0000_0381);
+        cpu.bus.write_word(src + 4, 0x0003_0201);
+
+        swi(&mut cpu, 0x16, [src, dst, 0, 0]);
+        assert_eq!(cpu.bus.read_word(dst) & 0xFF_FFFF, 0x06_0301);
+
+        // RegisterRamReset: EWRAM only
+        swi(&mut cpu, 0x01, [0x01, 0, 0, 0]);
+        assert_eq!(cpu.bus.read_word(src), 0);
+        assert_ne!(cpu.bus.read_word(dst), 0, "IWRAM kept");
+
+        // CpuSet at the end of the address space, the source wraps around to the BIOS
+        let words = [0xFFFF_FFFC, 0, 4, 8].map(|address| cpu.bus.read_word(address));
+        (0..5).for_each(|i| cpu.bus.write_word(dst + i * 4, 0xDEAD_BEEF));
+
+        swi(&mut cpu, 0x0B, [0xFFFF_FFFC, dst, 4 | (1 << 26), 0]);
+        assert_eq!(
+            [0, 4, 8, 12].map(|offset| cpu.bus.read_word(dst + offset)),
+            words
+        );
+        assert_eq!(
+            cpu.bus.read_word(dst + 16),
+            0xDEAD_BEEF,
+            "nothing past the count"
+        );
+    }
+}
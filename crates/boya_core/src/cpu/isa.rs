@@ -263,8 +263,11 @@ impl Arm7tdmi {
         self.branch_op(Condition::LE, offset.into())
     }
 
-    pub fn swi(&mut self) -> Cycle {
-        self.handle_exception(Exception::SoftwareInterrupt)
+    pub fn swi(&mut self, comment: u8) -> Cycle {
+        match self.hle_bios {
+            true => self.hle_swi(comment),
+            false => self.handle_exception(Exception::SoftwareInterrupt),
+        }
     }
 
     pub fn b(&mut self, offset: i32) -> Cycle {
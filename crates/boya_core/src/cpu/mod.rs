@@ -1,5 +1,6 @@
 pub mod arm;
 pub mod common;
+pub mod hle;
 pub mod isa;
 pub mod ops;
 pub mod pipeline;
@@ -21,7 +22,11 @@ use crate::{
         isa::Instruction,
         register::Register,
     },
-    utils::{Reset, bitflags::BitIter},
+    utils::{
+        Reset,
+        bitflags::BitIter,
+        savestate::{Savestate, StateError, StateReader, StateWriter},
+    },
 };
 
 #[derive(Default)]
@@ -29,6 +34,10 @@ pub struct Arm7tdmi {
     pub registers: Register,
     pub pipeline: Pipeline,
     pub bus: GbaBus,
+    /// Services SWIs natively instead of jumping into the BIOS.
+    pub hle_bios: bool,
+
+    hle_intr_wait: bool,
 }
 
 impl Arm7tdmi {
@@ -37,9 +46,17 @@ impl Arm7tdmi {
             registers: Register::default(),
             pipeline: Pipeline::default(),
             bus,
+            hle_bios: false,
+            hle_intr_wait: false,
         }
     }
 
+    /// Takes over the host configuration of the CPU this one replaces, used when loading a state.
+    pub fn inherit(&mut self, previous: Arm7tdmi) {
+        self.hle_bios = previous.hle_bios;
+        self.bus.inherit(previous.bus);
+    }
+
     pub fn step(&mut self) -> Cycle {
         let instruction = self.pipeline.take();
         let cycles = self.exec(instruction);
@@ -52,7 +69,7 @@ impl Arm7tdmi {
     #[inline]
     pub fn fetch(&mut self) -> u32 {
         let offset = self.instr_size();
-        let word = self.bus.read_word(self.pc());
+        let word = self.bus.fetch_opcode(self.pc(), self.is_thumb());
 
         self.registers.shift_pc(offset.into());
         word
@@ -134,13 +151,27 @@ impl Arm7tdmi {
         self.registers.set_pc(value);
     }
 
-    fn pre_fetch_cycle(&self, access_kind: MemoryAccess) -> Cycle {
+    fn pre_fetch_cycle(&mut self, access_kind: MemoryAccess) -> Cycle {
+        let dt = match self.is_thumb() {
+            true => DataType::HWord,
+            false => DataType::Word,
+        };
+
+        self.bus.fetch_cycle(self.pc(), dt, access_kind)
+    }
+
+    /// Sequential fetches of the two opcodes filling the pipeline after a jump.
+    fn refill_cycle(&mut self) -> Cycle {
         let dt = match self.is_thumb() {
             true => DataType::HWord,
             false => DataType::Word,
         };
+        let pc = self.pc();
 
-        self.bus.rw_cycle(self.pc(), dt, access_kind)
+        self.bus.fetch_cycle(pc, dt, MemoryAccess::Seq)
+            + self
+                .bus
+                .fetch_cycle(pc + dt.size() as u32, dt, MemoryAccess::Seq)
     }
 
     fn count_rlist(&self, rlist: u16) -> u8 {
@@ -242,6 +273,25 @@ impl Reset for Arm7tdmi {
         self.registers = Register::default();
         self.registers.cpsr = Psr::default();
         self.pipeline = Pipeline::default();
+        self.hle_intr_wait = false;
         self.bus.reset();
     }
 }
+
+impl Savestate for Arm7tdmi {
+    fn save_state(&self, writer: &mut StateWriter) {
+        self.registers.save_state(writer);
+        self.pipeline.save_state(writer);
+        self.bus.save_state(writer);
+        self.hle_intr_wait.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        self.registers.load_state(reader)?;
+        self.pipeline.load_state(reader)?;
+        self.bus.load_state(reader)?;
+        self.hle_intr_wait.load_state(reader)?;
+        self.redecode_pipeline();
+        Ok(())
+    }
+}
@@ -129,9 +129,9 @@ impl Arm7tdmi {
         self.registers.set_pc(value);
         self.pipeline.flush();
 
-        let fetch_cycle = self.pre_fetch_cycle(MemoryAccess::Seq);
+        let fetch_cycle = self.refill_cycle();
 
-        first_cycle + fetch_cycle.repeat(2)
+        first_cycle + fetch_cycle
     }
 
     pub fn mul_op(
@@ -225,11 +225,11 @@ impl Arm7tdmi {
 
         let value = match kind {
             DataType::HWord if signed => {
-                (self.bus.read_hword(addr & !1) as i16 >> ((addr & 1) * 8)) as i32 as u32
+                (self.bus.load_hword(addr & !1) as i16 >> ((addr & 1) * 8)) as i32 as u32
             }
             DataType::Byte if signed => self.bus.read_byte(addr) as i8 as i32 as u32,
             DataType::Byte => self.bus.read_byte(addr).into(),
-            DataType::HWord => (self.bus.read_hword(addr & !1) as u32).rotate_right((addr & 1) * 8),
+            DataType::HWord => (self.bus.load_hword(addr & !1) as u32).rotate_right((addr & 1) * 8),
             DataType::Word => self.bus.read_word(addr & !3).rotate_right((addr & 3) * 8),
         };
 
@@ -243,7 +243,7 @@ impl Arm7tdmi {
             _ => {}
         };
 
-        let read_cycle = self.bus.rw_cycle(addr, kind, MemoryAccess::NonSeq);
+        let read_cycle = self.bus.data_cycle(addr, kind, MemoryAccess::NonSeq);
         let internal_cycle = Cycle::internal(1);
         let pre_fetch_cycle = self.pre_fetch_cycle(MemoryAccess::Seq);
 
@@ -290,7 +290,7 @@ impl Arm7tdmi {
             _ => {}
         };
 
-        let write_cycle = self.bus.rw_cycle(addr, kind, MemoryAccess::NonSeq);
+        let write_cycle = self.bus.data_cycle(addr, kind, MemoryAccess::NonSeq);
 
         fetch_cycle + write_cycle
     }
@@ -336,7 +336,7 @@ impl Arm7tdmi {
                 false => MemoryAccess::NonSeq,
             };
 
-            write_cycle += self.bus.rw_cycle(offset, DataType::Word, access);
+            write_cycle += self.bus.data_cycle(offset, DataType::Word, access);
 
             self.store_reg(idx, &mut offset, usr);
         }
@@ -379,7 +379,9 @@ impl Arm7tdmi {
                 pc_dst = true;
             }
 
-            read_cycle += self.bus.rw_cycle(offset, DataType::Word, MemoryAccess::Seq);
+            read_cycle += self
+                .bus
+                .data_cycle(offset, DataType::Word, MemoryAccess::Seq);
 
             self.load_reg(idx, &mut offset, usr);
         }
@@ -409,9 +411,9 @@ impl Arm7tdmi {
             self.pipeline.flush();
         }
 
-        let extra_cycle = self.pre_fetch_cycle(MemoryAccess::Seq);
+        let extra_cycle = self.refill_cycle();
 
-        first_cycle + extra_cycle.repeat(2)
+        first_cycle + extra_cycle
     }
 
     pub fn branch_long_first_op(&mut self, nn: u16) -> Cycle {
@@ -436,9 +438,9 @@ impl Arm7tdmi {
         self.registers.set(Register::LR, lr, op_mode);
         self.pipeline.flush();
 
-        let extra_cycle = self.pre_fetch_cycle(MemoryAccess::Seq);
+        let extra_cycle = self.refill_cycle();
 
-        first_cycle + extra_cycle.repeat(2)
+        first_cycle + extra_cycle
     }
 
     pub fn handle_exception(&mut self, exception: Exception) -> Cycle {
@@ -467,9 +469,9 @@ impl Arm7tdmi {
             self.sync_pipeline();
         }
 
-        let extra_cycle = self.pre_fetch_cycle(MemoryAccess::Seq);
+        let extra_cycle = self.refill_cycle();
 
-        first_cycle + extra_cycle.repeat(2)
+        first_cycle + extra_cycle
     }
 
     pub fn store_psr_op(&mut self, rd: u8, kind: PsrKind) -> Cycle {
@@ -519,7 +521,7 @@ impl Arm7tdmi {
         }
 
         let dt = if byte { DataType::Byte } else { DataType::Word };
-        let rw_cycle = self.bus.rw_cycle(addr, dt, MemoryAccess::NonSeq);
+        let rw_cycle = self.bus.data_cycle(addr, dt, MemoryAccess::NonSeq);
         let internal_cycle = Cycle::internal(1);
         let fetch_cycle = self.pre_fetch_cycle(MemoryAccess::Seq);
 
@@ -572,7 +574,7 @@ impl Arm7tdmi {
         result
     }
 
-    fn data_op_cycle(&self, dst: Option<u8>, operand: &Operand) -> (Cycle, bool) {
+    fn data_op_cycle(&mut self, dst: Option<u8>, operand: &Operand) -> (Cycle, bool) {
         let reg_shift = operand.shift.as_ref().filter(|s| s.register).is_some();
         let pc_dst = dst.filter(|r| *r == NamedRegister::PC as u8).is_some();
 
@@ -586,9 +588,9 @@ impl Arm7tdmi {
         (cycle, pc_dst)
     }
 
-    fn extra_fetch_cycle(&self, cond: bool) -> Cycle {
+    fn extra_fetch_cycle(&mut self, cond: bool) -> Cycle {
         if cond {
-            self.pre_fetch_cycle(MemoryAccess::Seq).repeat(2)
+            self.refill_cycle()
         } else {
             Cycle::default()
         }
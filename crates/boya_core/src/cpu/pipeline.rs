@@ -1,3 +1,5 @@
+use crate::utils::savestate::impl_savestate;
+
 use super::{Arm7tdmi, Instruction};
 
 #[derive(Default)]
@@ -47,6 +49,11 @@ impl Arm7tdmi {
         self.pipeline.next_pc = self.pc();
     }
 
+    /// Decodes the current word again, decoded instructions are not part of save states.
+    pub fn redecode_pipeline(&mut self) {
+        self.pipeline.curr_instr = self.pipeline.curr_word.map(|word| self.decode(word));
+    }
+
     #[inline]
     pub fn sync_pipeline(&mut self) {
         if self.pipeline.next_address() != self.pc() {
@@ -57,3 +64,10 @@ impl Arm7tdmi {
         self.load_pipeline();
     }
 }
+
+impl_savestate!(Pipeline {
+    curr_pc,
+    next_pc,
+    curr_word,
+    next_word
+});
@@ -1,6 +1,9 @@
 use crate::{
     cpu::common::{Condition, OperatingMode},
-    utils::bitflags::Bitflag,
+    utils::{
+        bitflags::Bitflag,
+        savestate::{Savestate, StateError, StateReader, StateWriter},
+    },
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -138,3 +141,13 @@ impl From<u8> for PsrField {
         }
     }
 }
+
+impl Savestate for Psr {
+    fn save_state(&self, writer: &mut StateWriter) {
+        self.0.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        self.0.load_state(reader)
+    }
+}
@@ -1,4 +1,7 @@
-use crate::cpu::{common::OperatingMode, psr::Psr};
+use crate::{
+    cpu::{common::OperatingMode, psr::Psr},
+    utils::savestate::impl_savestate,
+};
 
 #[derive(Default)]
 pub struct Register {
@@ -87,3 +90,14 @@ impl Register {
         }
     }
 }
+
+impl_savestate!(Register {
+    main,
+    fiq,
+    svc,
+    abt,
+    irq,
+    und,
+    spsr,
+    cpsr
+});
@@ -21,7 +21,7 @@ impl From<u16> for Instruction {
 
 impl Executable for Instruction {
     fn dispatch(self, cpu: &mut Arm7tdmi) -> Cycle {
-        cpu.swi()
+        cpu.swi(self.nn)
     }
 }
 
@@ -309,6 +309,134 @@ impl RegisterEntry {
         }
     }
 
+    const fn sound_sweep() -> Self {
+        RegisterEntry {
+            name: "SOUND1CNT_L",
+            address: 0x060,
+            size: RegisterSize::HWord,
+            flags: const {
+                &[
+                    Flag::new("Number of Sweep Shift", 0, 3),
+                    Flag::new("Sweep Frequency Direction", 3, 1)
+                        .map(&[(0, "Increase"), (1, "Decrease")]),
+                    Flag::new("Sweep Time", 4, 3),
+                    Flag::unused(7, 9),
+                ]
+            },
+        }
+    }
+
+    const fn sound_duty(name: &'static str, address: u32) -> Self {
+        RegisterEntry {
+            name,
+            address,
+            size: RegisterSize::HWord,
+            flags: const {
+                &[
+                    Flag::new("Sound Length", 0, 6),
+                    Flag::new("Wave Pattern Duty", 6, 2).map(&[
+                        (0, "12.5%"),
+                        (1, "25%"),
+                        (2, "50%"),
+                        (3, "75%"),
+                    ]),
+                    Flag::new("Envelope Step-Time", 8, 3),
+                    Flag::new("Envelope Direction", 11, 1).map(&[(0, "Decrease"), (1, "Increase")]),
+                    Flag::new("Initial Volume of envelope", 12, 4),
+                ]
+            },
+        }
+    }
+
+    const fn sound_freq(name: &'static str, address: u32) -> Self {
+        RegisterEntry {
+            name,
+            address,
+            size: RegisterSize::HWord,
+            flags: const {
+                &[
+                    Flag::new("Frequency", 0, 11),
+                    Flag::unused(11, 3),
+                    Flag::new("Length Flag", 14, 1),
+                    Flag::new("Initial", 15, 1),
+                ]
+            },
+        }
+    }
+
+    const fn sound3cnt_l() -> Self {
+        RegisterEntry {
+            name: "SOUND3CNT_L",
+            address: 0x070,
+            size: RegisterSize::HWord,
+            flags: const {
+                &[
+                    Flag::unused(0, 5),
+                    Flag::new("Wave RAM Dimension", 5, 1).map(&[(0, "One bank"), (1, "Two banks")]),
+                    Flag::new("Wave RAM Bank Number", 6, 1),
+                    Flag::new("Sound Channel 3 Playback", 7, 1),
+                    Flag::unused(8, 8),
+                ]
+            },
+        }
+    }
+
+    const fn sound3cnt_h() -> Self {
+        RegisterEntry {
+            name: "SOUND3CNT_H",
+            address: 0x072,
+            size: RegisterSize::HWord,
+            flags: const {
+                &[
+                    Flag::new("Sound Length", 0, 8),
+                    Flag::unused(8, 5),
+                    Flag::new("Sound Volume", 13, 2).map(&[
+                        (0, "0%"),
+                        (1, "100%"),
+                        (2, "50%"),
+                        (3, "25%"),
+                    ]),
+                    Flag::new("Force Volume 75%", 15, 1),
+                ]
+            },
+        }
+    }
+
+    const fn sound4cnt_l() -> Self {
+        RegisterEntry {
+            name: "SOUND4CNT_L",
+            address: 0x078,
+            size: RegisterSize::HWord,
+            flags: const {
+                &[
+                    Flag::new("Sound Length", 0, 6),
+                    Flag::unused(6, 2),
+                    Flag::new("Envelope Step-Time", 8, 3),
+                    Flag::new("Envelope Direction", 11, 1).map(&[(0, "Decrease"), (1, "Increase")]),
+                    Flag::new("Initial Volume of envelope", 12, 4),
+                ]
+            },
+        }
+    }
+
+    const fn sound4cnt_h() -> Self {
+        RegisterEntry {
+            name: "SOUND4CNT_H",
+            address: 0x07C,
+            size: RegisterSize::HWord,
+            flags: const {
+                &[
+                    Flag::new("Dividing Ratio of Frequencies", 0, 3),
+                    Flag::new("Counter Step/Width", 3, 1).map(&[(0, "15 bits"), (1, "7 bits")]),
+                    Flag::new("Shift Clock Frequency", 4, 4),
+                    Flag::unused(8, 6),
+                    Flag::new("Length Flag", 14, 1),
+                    Flag::new("Initial", 15, 1),
+                ]
+            },
+        }
+    }
+
     const fn soundcnt_l() -> Self {
         RegisterEntry {
             name: "SOUNDCNT_L",
@@ -320,8 +448,8 @@ impl RegisterEntry {
                     Flag::unused(3, 1),
                     Flag::new("Sound 1-4 Master Volume LEFT", 4, 3),
                     Flag::unused(7, 1),
-                    Flag::new("Sound 1-4 Enable Flags LEFT", 8, 4),
-                    Flag::new("Sound 1-4 Enable Flags RIGHT", 12, 4),
+                    Flag::new("Sound 1-4 Enable Flags RIGHT", 8, 4),
+                    Flag::new("Sound 1-4 Enable Flags LEFT", 12, 4),
                 ]
             },
         }
@@ -356,6 +484,25 @@ impl RegisterEntry {
         }
     }
 
+    const fn soundcnt_x() -> Self {
+        RegisterEntry {
+            name: "SOUNDCNT_X",
+            address: 0x084,
+            size: RegisterSize::HWord,
+            flags: const {
+                &[
+                    Flag::new("Sound 1 ON flag", 0, 1),
+                    Flag::new("Sound 2 ON flag", 1, 1),
+                    Flag::new("Sound 3 ON flag", 2, 1),
+                    Flag::new("Sound 4 ON flag", 3, 1),
+                    Flag::unused(4, 3),
+                    Flag::new("PSG/FIFO Master Enable", 7, 1),
+                    Flag::unused(8, 8),
+                ]
+            },
+        }
+    }
+
     const fn sg_bias() -> Self {
         RegisterEntry {
             name: "SG_BIAS",
@@ -647,8 +794,19 @@ pub const IO_REGISTERS: &[RegisterEntry] = &[
     RegisterEntry::bldcnt(),
     RegisterEntry::bldalpha(),
     RegisterEntry::bldy(),
+    RegisterEntry::sound_sweep(),
+    RegisterEntry::sound_duty("SOUND1CNT_H", 0x062),
+    RegisterEntry::sound_freq("SOUND1CNT_X", 0x064),
+    RegisterEntry::sound_duty("SOUND2CNT_L", 0x068),
+    RegisterEntry::sound_freq("SOUND2CNT_H", 0x06C),
+    RegisterEntry::sound3cnt_l(),
+    RegisterEntry::sound3cnt_h(),
+    RegisterEntry::sound_freq("SOUND3CNT_X", 0x074),
+    RegisterEntry::sound4cnt_l(),
+    RegisterEntry::sound4cnt_h(),
     RegisterEntry::soundcnt_l(),
     RegisterEntry::soundcnt_h(),
+    RegisterEntry::soundcnt_x(),
     RegisterEntry::sg_bias(),
     RegisterEntry::dma_ad("DMA0SAD", 0x0B0),
     RegisterEntry::dma_ad("DMA0DAD", 0x0B4),
@@ -15,6 +15,7 @@ impl Step {
             Step::Instruction(data) => data.cycles,
             Step::Dma(data) => data.cycles,
             Step::Interrupt(data) => data.cycles,
+            Step::Halt(cycles) => *cycles,
         }
     }
 }
@@ -24,6 +25,8 @@ pub enum Step {
     Instruction(InstructionResult),
     Dma(DmaResult),
     Interrupt(InterruptResult),
+    /// Bus clocked while the CPU is halted.
+    Halt(Cycle),
 }
 
 #[derive(Debug)]
@@ -1,5 +1,6 @@
 use crate::{
-    Gba,
+    Gba, HALT_CYCLES,
+    bus::types::Cycle,
     debug::cpu::types::Step,
     ppu::{
         object::Obj,
@@ -14,12 +15,20 @@ pub mod ppu;
 
 impl Gba {
     pub fn debug_step(&mut self) -> Step {
+        if self.stopped() {
+            self.cpu.bus.tick_stopped();
+            return Step::Halt(Cycle::internal(0));
+        }
+
         let step = self
             .cpu
             .try_irq()
             .map(Step::Interrupt)
             .or_else(|| self.cpu.bus.try_dma().map(Step::Dma))
-            .unwrap_or_else(|| Step::Instruction(self.cpu.debug_step()));
+            .unwrap_or_else(|| match self.halted() {
+                true => Step::Halt(Cycle::internal(HALT_CYCLES)),
+                false => Step::Instruction(self.cpu.debug_step()),
+            });
 
         let cycles = step.cycles();
 
@@ -33,6 +42,10 @@ impl Gba {
 
         while self.cpu.bus.ppu.scanline == initial_scanline {
             self.step();
+
+            if self.stopped() {
+                break;
+            }
         }
     }
 
@@ -54,11 +67,16 @@ impl Gba {
             }
 
             if state_switch && inital_state == self.rendering() {
+                self.end_frame();
                 break false; // frame completed
             }
 
             let step = self.debug_step();
 
+            if self.stopped() {
+                break false;
+            }
+
             if irq && matches!(step, Step::Interrupt(_)) {
                 return true;
             }
@@ -1,11 +1,15 @@
 use crate::{
-    bus::{BIOS_SIZE, types::Cycle},
-    cpu::{Arm7tdmi, common::Exception, psr::Psr},
-    utils::Reset,
+    bus::{BIOS_SIZE, backup::Backup, gpio::rtc::RtcClock, link::LinkTransport, types::Cycle},
+    cpu::{Arm7tdmi, common::Exception, hle::HLE_BIOS, psr::Psr},
+    rom::{CartridgeHeader, MAX_ROM_SIZE, RomError, database::GameEntry},
+    utils::{
+        Reset,
+        rewind::Rewind,
+        savestate::{Savestate, StateError, StateReader, StateWriter},
+    },
 };
 
-#[allow(clippy::all)]
-pub mod apu; // TODO: APU implmentation
+pub mod apu;
 pub mod bus;
 pub mod cpu;
 pub mod ppu;
@@ -18,49 +22,217 @@ pub mod rom;
 #[cfg(test)]
 mod test;
 
+/// Most bus cycles ticked in one step while the CPU is halted, the step ends early on wake-up.
+const HALT_CYCLES: u8 = 16;
+
 #[derive(Default)]
 pub struct Gba {
     pub cpu: Arm7tdmi,
     pub cycles: u64,
+    pub rewind: Rewind,
+
+    /// BIOS dump replaced by the HLE stub, restored when HLE is turned off.
+    bios_dump: Option<Box<[u8; BIOS_SIZE]>>,
+    idle_loop: Option<u32>,
+    /// Whether the idle loop checked its exit condition since the last wake-up.
+    idle_polled: bool,
 }
 
 impl Gba {
     pub fn load_bios(&mut self, bios: [u8; BIOS_SIZE]) {
-        self.cpu.bus.bios = bios;
+        match self.cpu.hle_bios {
+            true => self.bios_dump = Some(Box::new(bios)),
+            false => self.cpu.bus.bios = bios,
+        }
     }
 
-    pub fn load_rom(&mut self, rom: &[u8]) {
+    /// Configures the cartridge from the game database, or from the ROM contents for unknown
+    /// titles.
+    ///
+    /// The header is not validated: only the real BIOS checks it at boot, and homebrew such as the
+    /// tonc demos ships without a valid logo or checksum. See `load_rom_checked` for dumps.
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), RomError> {
+        if rom.len() > MAX_ROM_SIZE {
+            return Err(RomError::TooLarge(rom.len()));
+        }
+
+        let entry = GameEntry::detect(rom);
+
         self.cpu.bus.rom = rom.to_vec();
+        self.cpu.bus.mirror_rom = entry.mirror_rom;
+        self.cpu.bus.backup = Backup::new(entry.save_type, entry.save_size);
+        self.cpu.bus.gpio.attach(entry.gpio);
+        self.idle_loop = entry.idle_loop;
+        self.idle_polled = false;
+
+        Ok(())
+    }
+
+    /// Loads the cartridge only if its header would pass the boot checks of the BIOS.
+    pub fn load_rom_checked(&mut self, rom: &[u8]) -> Result<CartridgeHeader, RomError> {
+        let header = CartridgeHeader::parse(rom)?;
+
+        self.load_rom(rom)?;
+        Ok(header)
+    }
+
+    /// Overrides the ROM mirroring set by `load_rom`, for homebrew that is missing from the
+    /// database.
+    pub fn set_rom_mirroring(&mut self, enabled: bool) {
+        self.cpu.bus.mirror_rom = enabled;
+    }
+
+    /// Replaces the host clock read by the cartridge RTC.
+    pub fn set_rtc_clock(&mut self, clock: impl RtcClock + 'static) {
+        self.cpu.bus.gpio.set_clock(Box::new(clock));
+    }
+
+    /// Imports a `.sav` file for the detected backup device, call after `load_rom`.
+    pub fn load_save(&mut self, data: &[u8]) {
+        self.cpu.bus.backup.load(data);
+    }
+
+    pub fn save_data(&self) -> Vec<u8> {
+        self.cpu.bus.backup.data().to_vec()
+    }
+
+    /// Whether the game wrote to its backup memory since the last poll.
+    pub fn poll_save_dirty(&mut self) -> bool {
+        self.cpu.bus.poll_backup_dirty()
     }
 
+    /// Captures the whole machine except the BIOS and ROM, which have to be loaded beforehand.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+
+        self.cycles.save_state(&mut writer);
+        self.cpu.save_state(&mut writer);
+        self.idle_polled.save_state(&mut writer);
+        writer.finish()
+    }
+
+    /// Decodes into a fresh machine, so that a rejected state leaves the running one untouched.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut reader = StateReader::new(data)?;
+        let mut cycles = 0;
+        let mut cpu = Arm7tdmi::default();
+        let mut idle_polled = false;
+
+        cycles.load_state(&mut reader)?;
+        cpu.load_state(&mut reader)?;
+        idle_polled.load_state(&mut reader)?;
+
+        let previous = std::mem::replace(&mut self.cpu, cpu);
+        self.cpu.inherit(previous);
+        self.cycles = cycles;
+        self.idle_polled = idle_polled;
+
+        Ok(())
+    }
+
+    /// Keeps up to `capacity` snapshots taken every `interval` frames, 0 disables rewind.
+    pub fn set_rewind(&mut self, capacity: usize, interval: u32) {
+        self.rewind.configure(capacity, interval);
+    }
+
+    /// Restores the previous rewind snapshot, returns false when the history is exhausted.
+    pub fn step_back(&mut self) -> bool {
+        let Some(state) = self.rewind.pop().map(<[u8]>::to_vec) else {
+            return false;
+        };
+
+        self.load_state(&state).is_ok()
+    }
+
+    /// Replaces the BIOS with native SWI handlers, so that games can run without a BIOS dump.
+    pub fn set_hle_bios(&mut self, enabled: bool) {
+        if enabled == self.cpu.hle_bios {
+            return;
+        }
+
+        self.cpu.hle_bios = enabled;
+
+        if !enabled {
+            self.cpu.bus.bios = self.bios_dump.take().map_or([0; BIOS_SIZE], |bios| *bios);
+            return;
+        }
+
+        let bios = std::mem::replace(&mut self.cpu.bus.bios, [0; BIOS_SIZE]);
+
+        self.bios_dump = Some(Box::new(bios));
+
+        for (address, word) in HLE_BIOS {
+            self.cpu.bus.bios[address as usize..][..4].copy_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    /// Plugs a link cable into the serial port, see `LocalLink` to connect several consoles.
+    pub fn connect_link(&mut self, link: impl LinkTransport + 'static) {
+        self.cpu.bus.io.sio.connect(Box::new(link));
+    }
+
+    pub fn disconnect_link(&mut self) -> Option<Box<dyn LinkTransport>> {
+        self.cpu.bus.io.sio.disconnect()
+    }
+
+    /// Jumps straight to the cartridge entry point in HLE mode.
     pub fn boot(&mut self) -> Cycle {
+        if self.cpu.hle_bios {
+            self.skip_bios();
+            return Cycle::internal(0);
+        }
+
         self.cpu.handle_exception(Exception::Reset)
     }
 
     pub fn step(&mut self) {
+        if self.stopped() {
+            self.cpu.bus.tick_stopped();
+            return;
+        }
+
         let cycles = self
             .cpu
             .try_irq()
             .map(|irq| irq.cycles)
-            .or_else(|| self.cpu.bus.try_dma().map(|dma| dma.cycles))
-            .unwrap_or_else(|| self.cpu.step());
+            .or_else(|| self.cpu.bus.try_dma().map(|dma| dma.cycles));
 
-        self.sync(cycles);
+        if let Some(cycles) = cycles {
+            self.sync(cycles);
+            return;
+        }
+
+        self.try_idle();
+
+        match self.halted() {
+            true => self.sync_halted(),
+            false => {
+                let cycles = self.cpu.step();
+                self.sync(cycles);
+            }
+        }
     }
 
+    /// Returns right away in STOP mode, as the LCD is not refreshed until the system wakes up.
     pub fn step_frame(&mut self) {
+        if self.stopped() {
+            self.step();
+            return;
+        }
+
         self.step_visible_frame();
         self.step_vblank();
+        self.end_frame();
     }
 
     pub fn step_visible_frame(&mut self) {
-        while self.rendering() {
+        while self.rendering() && !self.stopped() {
             self.step();
         }
     }
 
     pub fn step_vblank(&mut self) {
-        while !self.rendering() {
+        while !self.rendering() && !self.stopped() {
             self.step();
         }
     }
@@ -76,6 +248,14 @@ impl Gba {
         self.cpu.override_pc(0x0800_0000);
     }
 
+    pub fn halted(&self) -> bool {
+        self.cpu.bus.io.halted()
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.cpu.bus.io.stopped()
+    }
+
     pub fn rendering(&self) -> bool {
         self.cpu.bus.ppu.rendering()
     }
@@ -88,6 +268,58 @@ impl Gba {
         self.cpu.bus.io.keypad.keyinput = value;
     }
 
+    /// Light reaching the solar sensor, from 0 (darkness) to 0xFF.
+    pub fn set_solar_level(&mut self, level: u8) {
+        self.cpu.bus.gpio.input.light = level;
+    }
+
+    /// Rotation rate read by the gyro sensor, 0 at rest.
+    pub fn set_gyro(&mut self, rate: i16) {
+        self.cpu.bus.gpio.input.gyro = rate;
+    }
+
+    /// Tilt read by the accelerometer, 0 when the console lies flat.
+    pub fn set_tilt(&mut self, x: i16, y: i16) {
+        self.cpu.bus.gpio.input.tilt_x = x;
+        self.cpu.bus.gpio.input.tilt_y = y;
+    }
+
+    /// Whether the cartridge rumble motor is spinning.
+    pub fn rumble(&self) -> bool {
+        self.cpu.bus.gpio.rumble()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.cpu.bus.apu.resampler.output_rate()
+    }
+
+    pub fn set_sample_rate(&mut self, rate: u32) {
+        self.cpu.bus.apu.resampler.set_output_rate(rate);
+    }
+
+    /// Number of stereo frames waiting to be drained.
+    pub fn pending_samples(&self) -> usize {
+        self.cpu.bus.apu.resampler.len()
+    }
+
+    /// Drains interleaved left/right samples into `buffer`, returns the number of samples written.
+    pub fn drain_samples(&mut self, buffer: &mut [i16]) -> usize {
+        let resampler = &mut self.cpu.bus.apu.resampler;
+        let mut written = 0;
+
+        for frame in buffer.chunks_exact_mut(2) {
+            let Some((left, right)) = resampler.pop() else {
+                break;
+            };
+
+            frame[0] = left;
+            frame[1] = right;
+            written += 2;
+        }
+
+        written
+    }
+
     pub fn bios(&self) -> &[u8] {
         &self.cpu.bus.bios
     }
@@ -122,7 +354,28 @@ impl Gba {
     }
 
     pub fn sram(&self) -> &[u8] {
-        self.cpu.bus.sram.as_slice()
+        self.cpu.bus.backup.data()
+    }
+
+    /// Halts on the idle loop of the game until the next interrupt, letting it run once after
+    /// waking up so that it can check whether to exit.
+    fn try_idle(&mut self) {
+        if self.idle_loop != Some(self.cpu.exec_address()) || self.halted() {
+            return;
+        }
+
+        if self.idle_polled && self.cpu.bus.io.ie != 0 {
+            self.cpu.bus.io.write_haltcnt(0);
+        }
+
+        self.idle_polled = !self.idle_polled;
+    }
+
+    fn end_frame(&mut self) {
+        if self.rewind.tick_frame() {
+            let state = self.save_state();
+            self.rewind.push(state);
+        }
     }
 
     fn sync(&mut self, cycles: Cycle) {
@@ -131,18 +384,36 @@ impl Gba {
         self.cpu.bus.tick(count);
         self.cycles += count as u64;
     }
+
+    /// Ticks the bus one cycle at a time, so that the IRQ waking the CPU up is taken right away.
+    fn sync_halted(&mut self) {
+        for _ in 0..HALT_CYCLES {
+            self.sync(Cycle::internal(1));
+
+            if !self.halted() {
+                break;
+            }
+        }
+    }
 }
 
 impl Reset for Gba {
     fn reset(&mut self) {
         self.cpu.reset();
         self.cycles = 0;
+        self.idle_polled = false;
+        self.rewind.clear();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Gba, bus::BIOS_SIZE};
+    use crate::{
+        Gba, StateError,
+        bus::{BIOS_SIZE, types::Cycle},
+        rom::RomError,
+        utils::Reset,
+    };
 
     const GBA_BIOS: &[u8; BIOS_SIZE] = include_bytes!("../../../bin/gba_bios.bin");
     const MAX_CYCLE: u64 = 100_000_000;
@@ -152,7 +423,7 @@ mod tests {
         let mut gba = Gba::default();
 
         gba.load_bios(*GBA_BIOS);
-        gba.load_rom(&[0; 8]);
+        gba.load_rom(&[0; 8]).unwrap();
         gba.boot();
 
         loop {
@@ -165,4 +436,321 @@ mod tests {
 
         assert_eq!(gba.cpu.exec_address(), 0x0800_0000);
     }
+
+    fn vram_rom() -> Vec<u8> {
+        // MOV     R0, #0x0400_0000
+        // MOV     R1, #0x0400
+        // ADD     R1, R1, #3
+        // STRH    R1, [R0]         ; mode 3, BG2
+        // MOV     R2, #0x0600_0000
+        // loop:
+        //     STRH    R3, [R2], #2
+        //     ADD     R3, R3, #1
+        //     B       loop
+        [
+            0xE3A00404u32,
+            0xE3A01B01,
+            0xE2811003,
+            0xE1C010B0,
+            0xE3A02406,
+            0xE0C230B2,
+            0xE2833001,
+            0xEAFFFFFC,
+        ]
+        .map(u32::to_le_bytes)
+        .concat()
+    }
+
+    #[test]
+    fn test_load_rom_checked() {
+        let mut gba = Gba::default();
+
+        assert_eq!(
+            gba.load_rom_checked(&vram_rom()).unwrap_err(),
+            RomError::Truncated
+        );
+        assert!(gba.cpu.bus.rom.is_empty(), "rejected");
+
+        assert!(
+            gba.load_rom(&vram_rom()).is_ok(),
+            "homebrew without a header"
+        );
+    }
+
+    #[test]
+    fn test_save_state() {
+        let rom = vram_rom();
+        let mut gba = Gba::default();
+
+        gba.load_rom(&rom).unwrap();
+        gba.skip_bios();
+
+        for _ in 0..50_000 {
+            gba.step();
+        }
+
+        assert!(gba.rendering(), "mid-frame");
+
+        let state = gba.save_state();
+        let mut restored = Gba::default();
+
+        restored.load_rom(&rom).unwrap();
+        restored.load_state(&state).unwrap();
+
+        assert_eq!(restored.save_state(), state, "byte-exact restore");
+
+        for _ in 0..2 {
+            gba.step_frame();
+            restored.step_frame();
+
+            assert_eq!(gba.frame_buffer(), restored.frame_buffer());
+        }
+
+        assert!(gba.frame_buffer().iter().any(|&b| b != 0), "frame drawn");
+
+        assert_eq!(gba.save_state(), restored.save_state());
+    }
+
+    #[test]
+    fn test_load_state_atomic() {
+        let mut gba = Gba::default();
+
+        gba.load_rom(&vram_rom()).unwrap();
+        gba.skip_bios();
+        gba.step_frame();
+
+        let mut state = gba.save_state();
+
+        gba.step_frame();
+
+        let current = gba.save_state();
+
+        state.pop();
+        assert_eq!(gba.load_state(&state), Err(StateError::UnexpectedEof));
+        assert_eq!(gba.save_state(), current, "untouched by a truncated state");
+        assert_eq!(gba.cpu.bus.rom, vram_rom());
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut gba = Gba::default();
+
+        gba.load_rom(&vram_rom()).unwrap();
+        gba.skip_bios();
+        gba.set_rewind(3, 1);
+
+        let mut states = Vec::new();
+
+        for _ in 0..4 {
+            gba.step_frame();
+            states.push(gba.save_state());
+        }
+
+        assert!(gba.step_back());
+        assert_eq!(gba.save_state(), states[2]);
+        assert!(gba.step_back());
+        assert_eq!(gba.save_state(), states[1]);
+        assert!(!gba.step_back(), "oldest snapshot dropped");
+
+        gba.step_frame();
+        assert_eq!(gba.save_state(), states[2], "deterministic replay");
+    }
+
+    #[test]
+    fn test_idle_loop() {
+        // MOV     R0, #0x0400_0000
+        // MOV     R1, #8
+        // STRH    R1, [R0, #4]     ; VBlank IRQ in DISPSTAT
+        // MOV     R1, #1
+        // ADD     R2, R0, #0x200
+        // STRH    R1, [R2]         ; IE
+        // STRH    R1, [R2, #8]     ; IME
+        // MSR     CPSR_c, #0xDF    ; IRQs masked, VBlank still ends HALT
+        // B       0x0800_0428      ; idle loop of the database entry
+        let mut rom = [
+            0xE3A00404u32,
+            0xE3A01008,
+            0xE1C010B4,
+            0xE3A01001,
+            0xE2802C02,
+            0xE1C210B0,
+            0xE1C210B8,
+            0xE321F0DF,
+            0xEA000100,
+        ]
+        .map(u32::to_le_bytes)
+        .concat();
+
+        rom.resize(0x42C, 0);
+        rom[0xAC..0xB0].copy_from_slice(b"AFXE");
+        rom[0x428..].copy_from_slice(&0xEAFF_FFFEu32.to_le_bytes()); // B .
+
+        let mut gba = Gba::default();
+
+        gba.load_rom(&rom).unwrap();
+        gba.skip_bios();
+
+        for _ in 0..20 {
+            if gba.halted() {
+                break;
+            }
+
+            gba.step();
+        }
+
+        assert!(gba.halted(), "halted on the second pass");
+        assert_eq!(gba.cpu.exec_address(), 0x0800_0428);
+
+        while gba.halted() {
+            gba.step();
+        }
+
+        gba.step();
+        assert!(!gba.halted(), "one pass to check the exit condition");
+        assert!(gba.idle_polled);
+
+        let mut restored = Gba::default();
+
+        restored.load_rom(&rom).unwrap();
+        restored.load_state(&gba.save_state()).unwrap();
+        assert!(restored.idle_polled, "saved");
+
+        gba.reset();
+        assert!(!gba.idle_polled, "reset");
+    }
+
+    #[test]
+    fn test_halt() {
+        // MOV     R0, #0x0400_0000
+        // MOV     R1, #8
+        // STRH    R1, [R0, #4]     ; VBlank IRQ in DISPSTAT
+        // MOV     R1, #1
+        // ADD     R2, R0, #0x200
+        // STRH    R1, [R2]         ; IE
+        // STRH    R1, [R2, #8]     ; IME
+        // MOV     R1, #0
+        // ADD     R2, R0, #0x300
+        // STRB    R1, [R2, #1]     ; HALTCNT
+        // B       #0
+        let rom = [
+            0xE3A00404u32,
+            0xE3A01008,
+            0xE1C010B4,
+            0xE3A01001,
+            0xE2802C02,
+            0xE1C210B0,
+            0xE1C210B8,
+            0xE3A01000,
+            0xE2802C03,
+            0xE5C21001,
+            0xEAFFFFFE,
+        ]
+        .map(u32::to_le_bytes)
+        .concat();
+
+        let mut gba = Gba::default();
+
+        gba.load_rom(&rom).unwrap();
+        gba.skip_bios();
+
+        while !gba.halted() {
+            gba.step();
+        }
+
+        let pc = gba.cpu.exec_address();
+
+        while gba.halted() {
+            gba.step();
+            assert_eq!(gba.cpu.exec_address(), pc, "CPU paused");
+        }
+
+        assert!(!gba.rendering(), "woken up by VBlank");
+    }
+
+    #[test]
+    fn test_hle_bios_toggle() {
+        let mut gba = Gba::default();
+
+        gba.load_bios(*GBA_BIOS);
+        gba.set_hle_bios(true);
+        assert_ne!(gba.cpu.bus.bios, *GBA_BIOS, "stub BIOS");
+
+        gba.set_hle_bios(false);
+        assert_eq!(gba.cpu.bus.bios, *GBA_BIOS, "dump restored");
+    }
+
+    #[test]
+    fn test_hle_intr_wait() {
+        // MOV     R0, #0x0400_0000
+        // ADD     R1, PC, #0x20
+        // STR     R1, [R0, #-4]    ; IRQ handler
+        // MOV     R1, #8
+        // STRH    R1, [R0, #4]     ; VBlank IRQ in DISPSTAT
+        // MOV     R1, #1
+        // ADD     R2, R0, #0x200
+        // STRH    R1, [R2]         ; IE
+        // SWI     #0x050000        ; VBlankIntrWait
+        // MOV     R5, #1
+        // B       #0
+        // handler:
+        //     MOV     R0, #0x0400_0000
+        //     ADD     R2, R0, #0x200
+        //     MOV     R1, #1
+        //     STRH    R1, [R2, #2]     ; IF
+        //     STRH    R1, [R0, #-8]    ; BIOS IF
+        //     BX      LR
+        let rom = [
+            0xE3A00404u32,
+            0xE28F1020,
+            0xE5001004,
+            0xE3A01008,
+            0xE1C010B4,
+            0xE3A01001,
+            0xE2802C02,
+            0xE1C210B0,
+            0xEF050000,
+            0xE3A05001,
+            0xEAFFFFFE,
+            0xE3A00404,
+            0xE2802C02,
+            0xE3A01001,
+            0xE1C210B2,
+            0xE14010B8,
+            0xE12FFF1E,
+        ]
+        .map(u32::to_le_bytes)
+        .concat();
+
+        let mut gba = Gba::default();
+
+        gba.set_hle_bios(true);
+        gba.load_rom(&rom).unwrap();
+        gba.boot();
+
+        for _ in 0..100 {
+            gba.step();
+        }
+
+        assert!(gba.halted());
+        assert_eq!(gba.cpu.registers.main[5], 0, "waiting for VBlank");
+
+        gba.sync(Cycle::internal(3)); // off any fixed-size chunk grid
+
+        while gba.halted() {
+            gba.step();
+        }
+
+        let ppu = &gba.cpu.bus.ppu;
+
+        assert_eq!(
+            (ppu.scanline, ppu.dot, ppu.divider),
+            (160, 1, 0),
+            "woken up on the first VBlank dot"
+        );
+
+        gba.step_frame();
+
+        assert!(!gba.halted());
+        assert_eq!(gba.cpu.registers.main[5], 1);
+    }
 }
@@ -5,7 +5,8 @@ use crate::{
         character::{CharacterData, CharacterKind},
         pixel::{Color15, PixelContext, PixelResult},
         registers::{
-            bgcnt::ColorMode,
+            bgcnt::{Bgcnt, ColorMode},
+            bgtrans::Bgtrans,
             dispcnt::{Background, BgMode},
         },
     },
@@ -95,16 +96,27 @@ impl Ppu {
             return None;
         }
 
-        let pixel = self.get_bg_pixel(x, y, bg)?;
+        let (mx, my) = self.get_bg_mosaic(x, y, bg);
+        let pixel = self.get_bg_pixel(mx, my, bg)?;
 
+        Some(self.get_bg_blend_target(pixel, bg, ctx))
+    }
+
+    /// Sorts a visible background pixel into the color effect targets.
+    pub fn get_bg_blend_target(
+        &self,
+        pixel: Color15,
+        bg: Background,
+        ctx: &PixelContext,
+    ) -> PixelResult {
         if !self.window_fx_enable(ctx.window) {
-            Some(PixelResult::Top(pixel))
+            PixelResult::Top(pixel)
         } else if self.registers.bldcnt.is_bg_second_target(bg) && ctx.acc.top.is_some() {
-            Some(PixelResult::Bottom(pixel))
+            PixelResult::Bottom(pixel)
         } else if self.registers.bldcnt.is_bg_first_target(bg) && ctx.acc.top.is_none() {
-            Some(PixelResult::BlendTop(pixel))
+            PixelResult::BlendTop(pixel)
         } else {
-            Some(PixelResult::Top(pixel))
+            PixelResult::Top(pixel)
         }
     }
 
@@ -136,42 +148,18 @@ impl Ppu {
         let bgcnt = self.registers.bgcnt[bg_idx];
 
         let base_screen_offset = bgcnt.screen_block_offset();
-        let base_char_offset = bgcnt.char_block_offset();
         let screen_mode = bgcnt.screen_mode();
 
         let partial_data = match bg_kind {
             BgKind::Text => {
-                let bgofs = self.registers.bgofs[bg_idx];
-                let (width, height) = screen_mode.text_size();
-
-                let ox = (x + bgofs.x) % width;
-                let oy = (y + bgofs.y) % height;
-                let screen_x = (ox / 8) as u32;
-                let screen_y = (oy / 8) as u32;
-                let tile_x = screen_x % 32;
-                let tile_y = screen_y % 32;
-
-                let block_id = match (width, height) {
-                    (512, 256) => screen_x / 32,
-                    (256, 512) => screen_y / 32,
-                    (512, 512) => (screen_x / 32) + (screen_y / 32) * 2,
-                    _ => 0,
-                };
-
-                let bg_screen_size = 2;
-                let block_size = 32 * 32 * bg_screen_size;
-
-                let block_address = base_screen_offset + block_id * block_size;
-                let local_tile_id = tile_y * 32 + tile_x;
-                let screen_block_offset = block_address + local_tile_id * bg_screen_size;
-                let raw_bg_screen = self.vram.read_hword(screen_block_offset);
+                let (ox, oy) = self.get_text_offset(x, y, bg);
 
                 PartialCharData {
                     x: ox % 8,
                     y: oy % 8,
                     transform: None,
                     color_mode: bgcnt.color_mode(),
-                    bg_screen: BgScreen::text(raw_bg_screen),
+                    bg_screen: self.get_text_screen(bg, ox, oy),
                 }
             }
             BgKind::Affine => {
@@ -183,7 +171,7 @@ impl Ppu {
                     _ => unreachable!(),
                 };
 
-                let (ox, oy) = bgtrans.params.map(x.into(), y.into());
+                let (ox, oy) = self.map_affine_bg(bgtrans, x, y);
 
                 let tile_x = (ox % width) / 8;
                 let tile_y = (oy % height) / 8;
@@ -203,21 +191,76 @@ impl Ppu {
         };
 
         let char_data = CharacterData {
-            name: partial_data.bg_screen.character,
-            base_offset: base_char_offset,
-            hflip: partial_data.bg_screen.hflip,
-            vflip: partial_data.bg_screen.vflip,
-            color_mode: partial_data.color_mode,
-            palette: partial_data.bg_screen.palette,
-            kind: CharacterKind::Background,
-            height: 8,
-            width: 8,
             transform: partial_data.transform,
+            ..Self::get_bg_char_data(bgcnt, partial_data.color_mode, partial_data.bg_screen)
         };
 
         self.get_char_pixel(partial_data.x, partial_data.y, &char_data)
     }
 
+    /// Scrolled position of a screen pixel in the text background, wrapped to its size.
+    pub(super) fn get_text_offset(&self, x: u16, y: u16, bg: Background) -> (u16, u16) {
+        let bg_idx = bg.to_index();
+        let bgofs = self.registers.bgofs[bg_idx];
+        let (width, height) = self.registers.bgcnt[bg_idx].screen_mode().text_size();
+
+        ((x + bgofs.x) % width, (y + bgofs.y) % height)
+    }
+
+    /// Screen entry of the tile containing the scrolled position.
+    pub(super) fn get_text_screen(&self, bg: Background, ox: u16, oy: u16) -> BgScreen {
+        let bgcnt = self.registers.bgcnt[bg.to_index()];
+        let (width, height) = bgcnt.screen_mode().text_size();
+
+        let screen_x = (ox / 8) as u32;
+        let screen_y = (oy / 8) as u32;
+        let tile_x = screen_x % 32;
+        let tile_y = screen_y % 32;
+
+        let block_id = match (width, height) {
+            (512, 256) => screen_x / 32,
+            (256, 512) => screen_y / 32,
+            (512, 512) => (screen_x / 32) + (screen_y / 32) * 2,
+            _ => 0,
+        };
+
+        let bg_screen_size = 2;
+        let block_size = 32 * 32 * bg_screen_size;
+
+        let block_address = bgcnt.screen_block_offset() + block_id * block_size;
+        let local_tile_id = tile_y * 32 + tile_x;
+        let screen_block_offset = block_address + local_tile_id * bg_screen_size;
+
+        BgScreen::text(self.vram.read_hword(screen_block_offset))
+    }
+
+    pub(super) fn get_bg_char_data(
+        bgcnt: Bgcnt,
+        color_mode: ColorMode,
+        bg_screen: BgScreen,
+    ) -> CharacterData {
+        CharacterData {
+            name: bg_screen.character,
+            base_offset: bgcnt.char_block_offset(),
+            hflip: bg_screen.hflip,
+            vflip: bg_screen.vflip,
+            color_mode,
+            palette: bg_screen.palette,
+            kind: CharacterKind::Background,
+            height: 8,
+            width: 8,
+            transform: None,
+        }
+    }
+
+    /// Affine backgrounds walk from the reference point of the current line, which is the
+    /// first line of the frame during VBlank.
+    fn map_affine_bg(&self, bgtrans: &Bgtrans, x: u16, y: u16) -> (u16, u16) {
+        let line = if self.rendering() { self.scanline } else { 0 };
+
+        bgtrans.map(x.into(), y as i32 - line as i32)
+    }
+
     fn get_bg_bmp_pixel(
         &self,
         x: u16,
@@ -236,7 +279,7 @@ impl Ppu {
         let buffer_size = width * height * pixel_size;
         let buffer_start = frame_buffer as usize * pixel_size;
         let buffer_slice = &self.vram[buffer_start..buffer_start + buffer_size];
-        let (tx, ty) = self.registers.bg2trans.params.map(x.into(), y.into());
+        let (tx, ty) = self.map_affine_bg(&self.registers.bg2trans, x, y);
         let idx = (ty as usize * width + tx as usize) * pixel_size;
 
         if (tx as usize >= width || ty as usize >= height) && !bgcnt.overflow_wrap() {
@@ -253,3 +296,56 @@ impl Ppu {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{bus::Bus, ppu::Ppu};
+
+    fn run_until(ppu: &mut Ppu, scanline: u8) {
+        while ppu.scanline != scanline {
+            ppu.step();
+        }
+    }
+
+    #[test]
+    fn test_affine_reference() {
+        let mut ppu = Ppu::default();
+
+        ppu.registers.write_hword(0x20, 0x100); // PA
+        ppu.registers.write_hword(0x22, 0x80); // PB
+        ppu.registers.write_hword(0x26, 0x100); // PD
+        ppu.registers.write_word(0x28, 0x1000); // BG2X
+
+        run_until(&mut ppu, 3);
+        let bgtrans = &ppu.registers.bg2trans;
+        assert_eq!(
+            (bgtrans.ref_x, bgtrans.ref_y),
+            (0x1180, 0x300),
+            "three lines"
+        );
+        assert_eq!(bgtrans.map(2, 0), (0x13, 0x3));
+        assert_eq!(bgtrans.map(0, -3), (0x10, 0x0), "mosaic line");
+
+        ppu.registers.write_word(0x28, 0x0FFF_FF00); // -1
+        let bgtrans = &ppu.registers.bg2trans;
+        assert_eq!(
+            (bgtrans.ref_x, bgtrans.ref_y),
+            (-0x100, 0x300),
+            "latched on write"
+        );
+
+        run_until(&mut ppu, 5);
+        assert_eq!(
+            ppu.registers.bg2trans.ref_x, 0,
+            "moved from the written value"
+        );
+
+        run_until(&mut ppu, 161);
+        let bgtrans = &ppu.registers.bg2trans;
+        assert_eq!(
+            (bgtrans.ref_x, bgtrans.ref_y),
+            (-0x100, 0),
+            "reloaded at VBlank"
+        );
+    }
+}
@@ -36,8 +36,8 @@ impl Ppu {
         let width = char.width as u16;
         let height = char.height as u16;
 
-        let (cx, cy) = if let Some(_transform) = &char.transform {
-            (x, y) // TODO: Always no transform for now
+        let (cx, cy) = if let Some(transform) = &char.transform {
+            transform.map(x.into(), y.into()) // out of the source area when negative
         } else {
             let cx = if char.hflip { width - x - 1 } else { x };
             let cy = if char.vflip { height - y - 1 } else { y };
@@ -74,6 +74,46 @@ impl Ppu {
         Some(color)
     }
 
+    /// Row of an untransformed 8x8 tile, decoded at once for the scanline renderer.
+    pub fn get_char_row(&self, y: u16, char: &CharacterData) -> [Option<Color15>; 8] {
+        let cy = if char.vflip { 7 - y } else { y };
+        let row_addr = self.get_pixel_address(0, cy, char);
+        let mut row = [None; 8];
+
+        let (bits, bpp, base_palette) = match char.color_mode {
+            ColorMode::Palette16 => (self.vram.read_word(row_addr) as u64, 4, char.palette),
+            ColorMode::Palette256 => {
+                let low = self.vram.read_word(row_addr) as u64;
+                let high = self.vram.read_word(row_addr + 4) as u64;
+                (low | high << 32, 8, 0)
+            }
+        };
+
+        if bits == 0 {
+            return row;
+        }
+
+        let mask = (1 << bpp) - 1;
+
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let cx = if char.hflip { 7 - x } else { x };
+            let rel_color_id = ((bits >> (cx * bpp)) & mask) as u8;
+
+            if rel_color_id == 0 {
+                continue;
+            }
+
+            let color_id = base_palette * 16 + rel_color_id;
+
+            *pixel = Some(match char.kind {
+                CharacterKind::Background => self.read_bg_palette(color_id),
+                CharacterKind::Object(_) => self.read_obj_palette(color_id),
+            });
+        }
+
+        row
+    }
+
     fn get_pixel_address(&self, x: u16, y: u16, char: &CharacterData) -> u32 {
         let tx = x / 8;
         let ty = y / 8;
@@ -1,20 +1,23 @@
 pub mod background;
 pub mod character;
+pub mod mosaic;
 pub mod object;
 pub mod pixel;
 pub mod registers;
+pub mod scanline;
 pub mod window;
 
 use crate::{
     bus::types::Interrupt,
     ppu::{
+        mosaic::MosaicCounter,
         object::ObjPool,
-        pixel::{Color15, Color24, PixelAccumulator, PixelContext, PixelResult},
+        pixel::{Color15, PixelAccumulator, PixelContext, PixelResult},
         registers::{
             PpuRegister, bldcnt::ColorFx, dispcnt::Background, dispstat::Dispstat, window::Window,
         },
     },
-    utils::Reset,
+    utils::{Reset, savestate::impl_savestate},
 };
 
 pub const PALETTE_RAM_SIZE: usize = 0x400; // 1kb
@@ -40,10 +43,33 @@ pub struct Ppu {
     pub mask_hblank: bool,
 
     pending_irq: Option<Interrupt>,
+    hblank_dma: bool,
+    vblank_dma: bool,
+    capture_dma: bool,
+    capture_end: bool,
     pipeline: RenderPipeline,
     frame_buffer: Box<[u8; FRAME_BUFFER_LEN]>,
 }
 
+impl_savestate!(Ppu {
+    palette,
+    oam,
+    vram,
+    registers,
+    dot,
+    scanline,
+    divider,
+    mask_vblank,
+    mask_hblank,
+    pending_irq,
+    hblank_dma,
+    vblank_dma,
+    capture_dma,
+    capture_end,
+    pipeline,
+    frame_buffer
+});
+
 impl Default for Ppu {
     fn default() -> Self {
         Self {
@@ -57,6 +83,10 @@ impl Default for Ppu {
             mask_vblank: false,
             mask_hblank: false,
             pending_irq: None,
+            hblank_dma: false,
+            vblank_dma: false,
+            capture_dma: false,
+            capture_end: false,
             pipeline: RenderPipeline::default(),
             frame_buffer: Box::new([0x00; FRAME_BUFFER_LEN]),
         }
@@ -77,6 +107,27 @@ impl Ppu {
         self.pending_irq.take()
     }
 
+    /// Whether HBlank started since the last poll, HBlank DMAs trigger on that edge only.
+    pub fn poll_hblank_dma(&mut self) -> bool {
+        std::mem::replace(&mut self.hblank_dma, false)
+    }
+
+    /// Whether VBlank started since the last poll.
+    pub fn poll_vblank_dma(&mut self) -> bool {
+        std::mem::replace(&mut self.vblank_dma, false)
+    }
+
+    /// Whether a scanline in 2..=161 started since the last poll, one video capture transfer per
+    /// line.
+    pub fn poll_capture_dma(&mut self) -> bool {
+        std::mem::replace(&mut self.capture_dma, false)
+    }
+
+    /// Whether scanline 162 started since the last poll, video capture stops there.
+    pub fn poll_capture_end(&mut self) -> bool {
+        std::mem::replace(&mut self.capture_end, false)
+    }
+
     pub fn get_frame_buffer(&self) -> &[u8; FRAME_BUFFER_LEN] {
         &self.frame_buffer
     }
@@ -99,31 +150,28 @@ impl Ppu {
         self.vram[self.vram_offset(address)] = value;
     }
 
-    pub fn write_pixel(&mut self) {
-        let x = self.dot;
-        let y = self.scanline as u16;
-        let idx = (y as usize * LCD_WIDTH + x as usize) * 4;
-        let color15 = self.get_pixel(x, y);
-        let color24 = Color24::from(color15);
-
-        self.frame_buffer[idx] = color24.r;
-        self.frame_buffer[idx + 1] = color24.g;
-        self.frame_buffer[idx + 2] = color24.b;
+    pub fn get_pixel(&self, x: u16, y: u16) -> Color15 {
+        self.compose_pixel(x, y, self.iter_layers(), |layer, ctx| match layer {
+            Layer::Object { level } => self.get_obj_pixel_result(x, y, level, ctx),
+            Layer::Background { bg } => self.get_bg_pixel_result(x, y, bg, ctx),
+        })
     }
 
-    pub fn get_pixel(&self, x: u16, y: u16) -> Color15 {
+    /// Walks the layers from front to back until the pixel and its blending target are found.
+    fn compose_pixel(
+        &self,
+        x: u16,
+        y: u16,
+        layers: impl IntoIterator<Item = Layer>,
+        mut get_layer_result: impl FnMut(Layer, &PixelContext) -> Option<PixelResult>,
+    ) -> Color15 {
         let mut ctx = PixelContext {
             window: self.get_current_win(x, y),
             ..Default::default()
         };
 
-        for layer in self.iter_layers() {
-            let result = match layer {
-                Layer::Object { level } => self.get_obj_pixel_result(x, y, level, &ctx),
-                Layer::Background { bg } => self.get_bg_pixel_result(x, y, bg, &ctx),
-            };
-
-            if let Some(result) = result {
+        for layer in layers {
+            if let Some(result) = get_layer_result(layer, &ctx) {
                 self.apply_pixel_result(result, &mut ctx);
 
                 if ctx.acc.is_done() {
@@ -149,10 +197,12 @@ impl Ppu {
         match self.dot {
             0 if self.scanline < 160 => {
                 self.registers.dispstat.clear(Dispstat::HBLANK);
+                self.step_mosaic();
                 self.load_obj_pool();
             }
             239..=306 if self.scanline < 160 => {
                 self.registers.dispstat.set(Dispstat::HBLANK);
+                self.hblank_dma |= self.dot == 239;
 
                 if !self.mask_hblank && self.registers.dispstat.has(Dispstat::HBLANK_IRQ) {
                     self.pending_irq = Some(Interrupt::HBlank);
@@ -167,17 +217,38 @@ impl Ppu {
             _ => {}
         }
 
-        if self.scanline < 160 && self.dot < 240 {
-            self.write_pixel();
+        if self.scanline < 160 && self.dot == 239 {
+            self.render_scanline();
         }
 
         self.dot += 1;
     }
 
     fn handle_scanline(&mut self) {
+        if self.dot == 0 {
+            match self.scanline {
+                2..=161 => self.capture_dma = true,
+                162 => self.capture_end = true,
+                _ => {}
+            }
+        }
+
+        match (self.scanline, self.dot) {
+            (0..160, 240) => {
+                self.registers.bg2trans.step();
+                self.registers.bg3trans.step();
+            }
+            (160, 0) => {
+                self.registers.bg2trans.latch();
+                self.registers.bg3trans.latch();
+            }
+            _ => {}
+        }
+
         match self.scanline {
             160..=227 => {
                 self.registers.dispstat.set(Dispstat::VBLANK);
+                self.vblank_dma |= self.scanline == 160 && self.dot == 0;
 
                 if !self.mask_vblank && self.registers.dispstat.has(Dispstat::VBLANK_IRQ) {
                     self.pending_irq = Some(Interrupt::VBlank);
@@ -271,12 +342,16 @@ impl Reset for Ppu {
         self.scanline = 0;
         self.divider = 0;
         self.pending_irq = None;
+        self.hblank_dma = false;
+        self.vblank_dma = false;
+        self.capture_dma = false;
+        self.capture_end = false;
         self.pipeline = RenderPipeline::default();
         self.frame_buffer.fill(0xFF);
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum Layer {
     Object { level: u8 },
     Background { bg: Background },
@@ -287,8 +362,18 @@ struct RenderPipeline {
     sorted_bg: [Background; 4],
     obj_pool: ObjPool,
     window_enabled: bool,
+    bg_mosaic: MosaicCounter,
+    obj_mosaic: MosaicCounter,
 }
 
+impl_savestate!(RenderPipeline {
+    sorted_bg,
+    obj_pool,
+    window_enabled,
+    bg_mosaic,
+    obj_mosaic
+});
+
 impl Default for RenderPipeline {
     fn default() -> Self {
         Self {
@@ -300,6 +385,8 @@ impl Default for RenderPipeline {
             ],
             obj_pool: ObjPool::default(),
             window_enabled: false,
+            bg_mosaic: MosaicCounter::default(),
+            obj_mosaic: MosaicCounter::default(),
         }
     }
 }
@@ -314,6 +401,15 @@ pub struct TransformParam {
     pub y: u32,
 }
 
+impl_savestate!(TransformParam {
+    pa,
+    pb,
+    pc,
+    pd,
+    x,
+    y
+});
+
 impl Default for TransformParam {
     fn default() -> Self {
         Self {
@@ -0,0 +1,118 @@
+use crate::{
+    ppu::{Ppu, object::Obj, registers::dispcnt::Background},
+    utils::savestate::impl_savestate,
+};
+
+/// Vertical mosaic counter, the same line is repeated until it reaches the block height.
+///
+/// Changing the height mid-frame keeps counting from the last latched line instead of
+/// realigning the blocks on the top of the screen.
+#[derive(Debug, Default)]
+pub struct MosaicCounter {
+    pub line: u8,
+    count: u8,
+}
+
+impl_savestate!(MosaicCounter { line, count });
+
+impl MosaicCounter {
+    fn step(&mut self, scanline: u8, size: u16) {
+        if scanline == 0 || self.count as u16 >= size {
+            self.line = scanline;
+            self.count = 0;
+        } else {
+            self.count += 1;
+        }
+    }
+}
+
+impl Ppu {
+    /// Updates the vertical mosaic counters at the start of a visible line.
+    pub fn step_mosaic(&mut self) {
+        let mosaic = &self.registers.mosaic;
+
+        self.pipeline
+            .bg_mosaic
+            .step(self.scanline, mosaic.bg_mosaic_vsize());
+        self.pipeline
+            .obj_mosaic
+            .step(self.scanline, mosaic.obj_mosaic_vsize());
+    }
+
+    /// Screen position sampled by the background, snapped to the top left of its mosaic block.
+    pub fn get_bg_mosaic(&self, x: u16, y: u16, bg: Background) -> (u16, u16) {
+        if !self.registers.bgcnt[bg.to_index()].mosaic_enabled() {
+            return (x, y);
+        }
+
+        let width = self.registers.mosaic.bg_mosaic_hsize() + 1;
+
+        (x - x % width, self.pipeline.bg_mosaic.line.into())
+    }
+
+    /// Position relative to the object, the edge pixels are stretched when the mosaic block
+    /// starts outside of it.
+    pub fn get_obj_mosaic(&self, x: u16, y: u16, obj: &Obj) -> (u16, u16) {
+        let cx = x.wrapping_sub(obj.x()) & 0x1FF;
+        let cy = y.wrapping_sub(obj.y().into()) & 0xFF;
+
+        if !obj.mosaic() {
+            return (cx, cy);
+        }
+
+        let (width, height) = obj.bounds();
+        let block_width = self.registers.mosaic.obj_mosaic_hsize() + 1;
+        let mx = (x - x % block_width).wrapping_sub(obj.x()) & 0x1FF;
+        let my = self.pipeline.obj_mosaic.line.wrapping_sub(obj.y());
+
+        let mx = if mx < width.into() { mx } else { 0 };
+        let my = if my < height { my } else { 0 };
+
+        (mx, my.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bus::Bus,
+        ppu::{Ppu, registers::dispcnt::Background},
+    };
+
+    #[test]
+    fn test_mosaic() {
+        let mut ppu = Ppu::default();
+
+        ppu.registers.mosaic.value = 0x1123; // BG 4x3, OBJ 2x2
+        ppu.registers.bgcnt[0].value = 1 << 6;
+        ppu.oam.write_hword(0, 0x1003); // y = 3, mosaic
+        ppu.oam.write_hword(2, 0x000A); // x = 10
+
+        let obj = ppu.get_object(0);
+        let mut lines = Vec::new();
+
+        for scanline in 0..8 {
+            ppu.scanline = scanline;
+            ppu.step_mosaic();
+
+            let (_, y) = ppu.get_bg_mosaic(5, scanline.into(), Background::Bg0);
+            lines.push(y);
+
+            if scanline == 3 {
+                assert_eq!(ppu.get_bg_mosaic(5, 3, Background::Bg1), (5, 3), "disabled");
+                assert_eq!(ppu.get_obj_mosaic(13, 3, &obj), (2, 0), "top row stretched");
+            }
+
+            if scanline == 5 {
+                assert_eq!(
+                    ppu.get_obj_mosaic(11, 5, &obj),
+                    (0, 1),
+                    "sampled from line 4"
+                );
+            }
+        }
+
+        assert_eq!(lines, [0, 0, 0, 3, 3, 3, 6, 6]);
+        assert_eq!(ppu.get_bg_mosaic(5, 7, Background::Bg0).0, 4);
+    }
+}
@@ -6,14 +6,21 @@ use crate::{
         pixel::{Color15, PixelContext, PixelResult},
         registers::{bgcnt::ColorMode, dispcnt::BgMode},
     },
-    utils::bitflags::Bitflag,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
 };
 
+/// OBJ render cycles available on each line.
+pub const OBJ_LINE_CYCLES: u32 = 1210;
+/// OBJ render cycles left when the H-Blank interval is kept free for VRAM and OAM accesses.
+pub const OBJ_LINE_CYCLES_HBLANK_FREE: u32 = 954;
+
 #[derive(Debug)]
 pub struct Obj {
     attr: [u16; 3],
 }
 
+impl_savestate!(Obj { attr });
+
 impl Obj {
     pub fn y(&self) -> u8 {
         self.attr[0].get_bits_u8(0, 7)
@@ -24,7 +31,12 @@ impl Obj {
     }
 
     pub fn double_size(&self) -> bool {
-        self.attr[0].has(9)
+        self.transform() && self.attr[0].has(9)
+    }
+
+    /// Bit 9 hides regular objects, it only selects the double size for affine ones.
+    pub fn disabled(&self) -> bool {
+        !self.transform() && self.attr[0].has(9)
     }
 
     /// # Panics
@@ -90,6 +102,36 @@ impl Obj {
         }
     }
 
+    /// Area covered on screen, twice the object dimmensions with the double size flag.
+    pub fn bounds(&self) -> (u8, u8) {
+        let (width, height) = self.dimmensions();
+        let scale = if self.double_size() { 2 } else { 1 };
+
+        (width * scale, height * scale)
+    }
+
+    /// Cycles taken to render the object on a line, affine objects read their parameters first
+    /// and take two cycles per pixel of their bounding box.
+    pub fn render_cycles(&self) -> u32 {
+        let (width, _height) = self.bounds();
+
+        match self.transform() {
+            true => 10 + width as u32 * 2,
+            false => width as u32,
+        }
+    }
+
+    /// Pixels of the bounding box drawn from the left with `cycles` left in the line budget.
+    pub fn render_width(&self, cycles: u32) -> u16 {
+        let (width, _height) = self.bounds();
+        let pixels = match self.transform() {
+            true => cycles.saturating_sub(10) / 2,
+            false => cycles,
+        };
+
+        pixels.min(width as u32) as u16
+    }
+
     pub fn character(&self) -> u16 {
         self.attr[2].get_bits(0, 9)
     }
@@ -118,17 +160,30 @@ impl Ppu {
         }
     }
 
+    /// The matrix rotates around the center of the bounding box, which maps to the center of
+    /// the object, so the reference point is moved by half the box from there.
     pub fn get_obj_transform_params(&self, obj: &Obj) -> TransformParam {
-        // FIXME
         let id = obj.transform_parameter() as u32;
+        let (width, height) = obj.dimmensions();
+        let (box_width, box_height) = obj.bounds();
+
+        let pa = self.oam.read_hword(id * 32 + 6);
+        let pb = self.oam.read_hword(id * 32 + 14);
+        let pc = self.oam.read_hword(id * 32 + 22);
+        let pd = self.oam.read_hword(id * 32 + 30);
+
+        let half_x = box_width as i32 / 2;
+        let half_y = box_height as i32 / 2;
+        let x = ((width as i32 / 2) << 8) - half_x * pa as i16 as i32 - half_y * pb as i16 as i32;
+        let y = ((height as i32 / 2) << 8) - half_x * pc as i16 as i32 - half_y * pd as i16 as i32;
 
         TransformParam {
-            pa: self.oam.read_hword(id * 32 + 6),
-            pb: self.oam.read_hword(id * 32 + 14),
-            pc: self.oam.read_hword(id * 32 + 22),
-            pd: self.oam.read_hword(id * 32 + 30),
-            x: 0,
-            y: 0,
+            pa,
+            pb,
+            pc,
+            pd,
+            x: x as u32,
+            y: y as u32,
         }
     }
 
@@ -143,15 +198,31 @@ impl Ppu {
 
         self.pipeline.obj_pool.clear();
 
+        let mut cycles = match self.registers.dispcnt.hblank_obj_proc() {
+            true => OBJ_LINE_CYCLES_HBLANK_FREE,
+            false => OBJ_LINE_CYCLES,
+        };
+
         for id in 0..128 {
             let obj = self.get_object(id);
-            let (_width, height) = obj.dimmensions();
-            let height = height * if obj.double_size() { 2 } else { 1 };
+            let (_width, height) = obj.bounds();
             let diff = self.scanline.wrapping_sub(obj.y());
 
-            if diff < height {
-                self.pipeline.obj_pool.push(obj);
+            if obj.disabled() || diff >= height {
+                continue;
             }
+
+            // the renderer stops where it runs out of cycles, partway through the last object
+            let Some(remaining) = cycles.checked_sub(obj.render_cycles()) else {
+                if obj.render_width(cycles) > 0 {
+                    self.pipeline.obj_pool.push_clipped(obj, cycles);
+                }
+
+                break;
+            };
+
+            cycles = remaining;
+            self.pipeline.obj_pool.push(obj);
         }
     }
 
@@ -160,24 +231,41 @@ impl Ppu {
 
         loop {
             let (id, obj) = self.pipeline.obj_pool.get(x, layer, offset)?;
-            let cx = x.wrapping_sub(obj.x()) & 0x1FF;
-            let cy = y.wrapping_sub(obj.y().into()) & 0xFF;
-
-            if let Some(pixel) = self.get_obj_pixel_inner(cx, cy, obj) {
-                let result = match obj.mode() {
-                    ObjMode::Window => ObjPixel::Window,
-                    ObjMode::Normal => ObjPixel::Normal(pixel),
-                    ObjMode::SemiTransparent => ObjPixel::SemiTransparent(pixel),
-                };
+            let char_data = self.get_obj_char_data(obj);
 
-                return Some(result);
+            if let Some(pixel) = self.get_obj_screen_pixel(x, y, obj, &char_data) {
+                return Some(pixel);
             } else {
                 offset = id + 1
             }
         }
     }
 
+    /// Pixel of the object at a screen position it covers.
+    pub fn get_obj_screen_pixel(
+        &self,
+        x: u16,
+        y: u16,
+        obj: &Obj,
+        char_data: &CharacterData,
+    ) -> Option<ObjPixel> {
+        let (cx, cy) = self.get_obj_mosaic(x, y, obj);
+        let pixel = self.get_char_pixel(cx, cy, char_data)?;
+
+        let result = match obj.mode() {
+            ObjMode::Window => ObjPixel::Window,
+            ObjMode::Normal => ObjPixel::Normal(pixel),
+            ObjMode::SemiTransparent => ObjPixel::SemiTransparent(pixel),
+        };
+
+        Some(result)
+    }
+
     pub fn get_obj_pixel_inner(&self, x: u16, y: u16, obj: &Obj) -> Option<Color15> {
+        self.get_char_pixel(x, y, &self.get_obj_char_data(obj))
+    }
+
+    pub fn get_obj_char_data(&self, obj: &Obj) -> CharacterData {
         let (width, height) = obj.dimmensions();
         let vram_mapping = self.registers.dispcnt.obj_vram_mapping();
 
@@ -191,7 +279,7 @@ impl Ppu {
             BgMode::Mode3 | BgMode::Mode4 | BgMode::Mode5 => 0x14000,
         };
 
-        let char_data = CharacterData {
+        CharacterData {
             name: obj.character(),
             color_mode: obj.color_mode(),
             palette: obj.palette(),
@@ -202,9 +290,7 @@ impl Ppu {
             vflip,
             transform,
             base_offset,
-        };
-
-        self.get_char_pixel(x, y, &char_data)
+        }
     }
 
     pub fn get_obj_pixel_result(
@@ -220,28 +306,33 @@ impl Ppu {
 
         let pixel = self.get_obj_pixel(x, y, level)?;
 
+        Some(self.get_obj_blend_target(pixel, ctx))
+    }
+
+    /// Sorts a visible object pixel into the color effect targets.
+    pub fn get_obj_blend_target(&self, pixel: ObjPixel, ctx: &PixelContext) -> PixelResult {
         match pixel {
             ObjPixel::Normal(pixel) if !self.window_fx_enable(ctx.window) => {
-                Some(PixelResult::Top(pixel))
+                PixelResult::Top(pixel)
             }
             ObjPixel::Normal(pixel)
                 if self.registers.bldcnt.is_obj_second_target() && ctx.acc.top.is_some() =>
             {
-                Some(PixelResult::Bottom(pixel))
+                PixelResult::Bottom(pixel)
             }
             ObjPixel::Normal(pixel)
                 if self.registers.bldcnt.is_obj_first_target() && ctx.acc.top.is_none() =>
             {
-                Some(PixelResult::BlendTop(pixel))
+                PixelResult::BlendTop(pixel)
             }
-            ObjPixel::Normal(pixel) => Some(PixelResult::Top(pixel)),
-            ObjPixel::SemiTransparent(pixel) => Some(PixelResult::BlendTop(pixel)),
-            ObjPixel::Window => Some(PixelResult::Window),
+            ObjPixel::Normal(pixel) => PixelResult::Top(pixel),
+            ObjPixel::SemiTransparent(pixel) => PixelResult::BlendTop(pixel),
+            ObjPixel::Window => PixelResult::Window,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum ObjPixel {
     Normal(Color15),
     SemiTransparent(Color15),
@@ -259,13 +350,18 @@ pub enum ObjMode {
 pub struct ObjPool {
     pool: [Obj; 128],
     len: usize,
+    /// Cycles left for the last object, when the line budget runs out before its end.
+    clip_cycles: Option<u32>,
 }
 
+impl_savestate!(ObjPool { pool, len, clip_cycles } if |pool| pool.len <= pool.pool.len());
+
 impl Default for ObjPool {
     fn default() -> Self {
         Self {
             pool: [const { Obj::placeholder() }; 128],
             len: 0,
+            clip_cycles: None,
         }
     }
 }
@@ -276,8 +372,31 @@ impl ObjPool {
         self.len += 1;
     }
 
+    fn push_clipped(&mut self, value: Obj, cycles: u32) {
+        self.push(value);
+        self.clip_cycles = Some(cycles);
+    }
+
     fn clear(&mut self) {
         self.len = 0;
+        self.clip_cycles = None;
+    }
+
+    /// Objects with the width they are drawn on, only the last one can be cut short.
+    pub fn iter(&self) -> impl Iterator<Item = (&Obj, u16)> {
+        self.pool[..self.len]
+            .iter()
+            .enumerate()
+            .map(|(i, obj)| (obj, self.width(i)))
+    }
+
+    fn width(&self, index: usize) -> u16 {
+        let obj = &self.pool[index];
+
+        match self.clip_cycles {
+            Some(cycles) if index + 1 == self.len => obj.render_width(cycles),
+            _ => obj.bounds().0 as u16,
+        }
     }
 
     fn get(&self, x: u16, layer: u8, offset: usize) -> Option<(usize, &Obj)> {
@@ -293,11 +412,9 @@ impl ObjPool {
             }
 
             if prio <= layer {
-                let (width, _height) = obj.dimmensions();
-                let width = width * if obj.double_size() { 2 } else { 1 };
                 let diff = x.wrapping_sub(obj.x()) & 0x1FF;
 
-                if diff < width as u16 {
+                if diff < self.width(offset + i) {
                     return Some((offset + i, obj));
                 }
             }
@@ -306,3 +423,158 @@ impl ObjPool {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bus::Bus,
+        ppu::{Ppu, object::ObjPool},
+        utils::savestate::{Savestate, StateError, StateReader, StateWriter},
+    };
+
+    fn affine_ppu(attr0: u16, matrix: [i16; 4]) -> Ppu {
+        let mut ppu = Ppu::default();
+
+        ppu.oam.write_hword(0, attr0 | 0x100); // affine 8x8, 16 colors
+        ppu.oam.write_hword(2, 0);
+        ppu.oam.write_hword(4, 0);
+
+        for (i, param) in matrix.into_iter().enumerate() {
+            ppu.oam.write_hword(6 + i as u32 * 8, param as u16);
+        }
+
+        // the color of each pixel is its column + 1
+        for row in 0..8 {
+            for (i, byte) in [0x21, 0x43, 0x65, 0x87].into_iter().enumerate() {
+                ppu.vram[0x10000 + row * 4 + i] = byte;
+            }
+        }
+
+        for color in 1..=8 {
+            ppu.palette.write_hword(512 + color * 2, color as u16);
+        }
+
+        ppu
+    }
+
+    fn column(ppu: &Ppu, x: u16, y: u16) -> Option<u16> {
+        let obj = ppu.get_object(0);
+
+        ppu.get_obj_pixel_inner(x, y, &obj)
+            .map(|color| color.r as u16 - 1)
+    }
+
+    #[test]
+    fn test_affine_obj() {
+        let ppu = affine_ppu(0x200, [0x80, 0, 0, 0x80]);
+
+        assert_eq!(ppu.get_object(0).bounds(), (16, 16));
+        assert_eq!(column(&ppu, 0, 0), Some(0), "scaled up twice");
+        assert_eq!(column(&ppu, 7, 0), Some(3));
+        assert_eq!(column(&ppu, 15, 15), Some(7));
+
+        let ppu = affine_ppu(0x200, [0x100, 0, 0, 0x100]);
+
+        assert_eq!(column(&ppu, 3, 8), None, "clipped to the source area");
+        assert_eq!(column(&ppu, 4, 4), Some(0));
+        assert_eq!(column(&ppu, 11, 11), Some(7));
+        assert_eq!(column(&ppu, 12, 8), None);
+
+        let ppu = affine_ppu(0, [0, -0x100, 0x100, 0]);
+
+        assert_eq!(column(&ppu, 0, 1), Some(7), "rotated by 90 degrees");
+        assert_eq!(column(&ppu, 5, 7), Some(1));
+        assert!(!ppu.get_object(0).double_size());
+    }
+
+    /// Number of pooled objects and the width of the last one.
+    fn pooled(ppu: &Ppu) -> (usize, u16) {
+        let pool = &ppu.pipeline.obj_pool;
+
+        (
+            pool.iter().count(),
+            pool.iter().last().map_or(0, |(_, w)| w),
+        )
+    }
+
+    #[test]
+    fn test_obj_cycle_budget() {
+        let mut ppu = Ppu::default();
+
+        for id in 0..128 {
+            ppu.oam.write_hword(id * 8, 0); // 64x64 at y = 0
+            ppu.oam.write_hword(id * 8 + 2, 0xC000);
+        }
+
+        ppu.registers.dispcnt.value = 0x1000;
+        ppu.load_obj_pool();
+        assert_eq!(pooled(&ppu), (19, 58), "64 cycles each, 58 left");
+
+        ppu.registers.dispcnt.value = 0x1020;
+        ppu.load_obj_pool();
+        assert_eq!(pooled(&ppu), (15, 58), "H-Blank interval free");
+
+        for id in 0..128 {
+            ppu.oam.write_hword(id * 8, 0x0300); // 32x32 affine, double size
+            ppu.oam.write_hword(id * 8 + 2, 0x8000);
+        }
+
+        ppu.registers.dispcnt.value = 0x1000;
+        ppu.load_obj_pool();
+        assert_eq!(pooled(&ppu), (9, 48), "138 cycles each, 106 left");
+
+        ppu.oam.write_hword(0, 0x0200); // disabled
+        ppu.oam.write_hword(8, 0x0350); // y = 80
+        ppu.load_obj_pool();
+        assert_eq!(pooled(&ppu), (9, 48), "only the objects on the line count");
+    }
+
+    #[test]
+    fn test_obj_partial_render() {
+        let mut ppu = Ppu::default();
+
+        for id in 0..19 {
+            ppu.oam.write_hword(id * 8, 0); // 64x64 at y = 0, off screen but for the last one
+            ppu.oam
+                .write_hword(id * 8 + 2, 0xC000 | if id < 18 { 0x100 } else { 0 });
+        }
+
+        for id in 19..128 {
+            ppu.oam.write_hword(id * 8, 0x0200);
+        }
+
+        ppu.vram[0x10000..].fill(0x11);
+        ppu.palette.write_hword(512 + 2, 0x7FFF);
+        ppu.registers.dispcnt.value = 0x1040;
+        ppu.load_obj_pool();
+
+        assert!(ppu.get_obj_pixel(57, 0, 3).is_some());
+        assert!(ppu.get_obj_pixel(58, 0, 3).is_none(), "out of cycles");
+
+        ppu.render_scanline();
+
+        let white = |x: usize| ppu.get_frame_buffer()[x * 4..][..3] == [0xFF; 3];
+
+        assert!(white(57));
+        assert!(!white(58), "cut short by the renderer");
+    }
+
+    #[test]
+    fn test_obj_pool_state_range() {
+        let mut writer = StateWriter::new();
+        let pool = ObjPool::default();
+
+        pool.pool.save_state(&mut writer);
+        129usize.save_state(&mut writer);
+        pool.clip_cycles.save_state(&mut writer);
+
+        let state = writer.finish();
+        let mut pool = ObjPool::default();
+        let mut reader = StateReader::new(&state).unwrap();
+
+        assert_eq!(
+            pool.load_state(&mut reader),
+            Err(StateError::InvalidValue("ObjPool"))
+        );
+    }
+}
@@ -1,10 +1,12 @@
-use crate::utils::bitflags::Bitflag;
+use crate::utils::{bitflags::Bitflag, savestate::impl_savestate};
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Bgcnt {
     pub value: u16,
 }
 
+impl_savestate!(Bgcnt { value });
+
 impl Bgcnt {
     pub fn bg_priority(&self) -> u8 {
         self.value.get_bits_u8(0, 1)
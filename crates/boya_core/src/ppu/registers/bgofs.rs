@@ -1,4 +1,4 @@
-use crate::bus::Bus;
+use crate::{bus::Bus, utils::savestate::impl_savestate};
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Bgofs {
@@ -6,9 +6,11 @@ pub struct Bgofs {
     pub y: u16,
 }
 
+impl_savestate!(Bgofs { x, y });
+
 impl Bus for Bgofs {
     fn read_byte(&self, _address: u32) -> u8 {
-        0 // TODO: open bus
+        0 // write-only, read as open bus by `PpuRegister::read`
     }
 
     fn write_byte(&mut self, address: u32, value: u8) {
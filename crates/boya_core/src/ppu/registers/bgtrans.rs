@@ -1,13 +1,51 @@
-use crate::{bus::Bus, ppu::TransformParam};
+use crate::{bus::Bus, ppu::TransformParam, utils::savestate::impl_savestate};
 
 #[derive(Debug, Default)]
 pub struct Bgtrans {
     pub params: TransformParam,
+    /// Internal reference point of the current line, reloaded from BGxX at VBlank or when
+    /// written to and moved by PB every line.
+    pub ref_x: i32,
+    /// Same as `ref_x` for BGxY, moved by PD.
+    pub ref_y: i32,
+}
+
+impl_savestate!(Bgtrans {
+    params,
+    ref_x,
+    ref_y
+});
+
+impl Bgtrans {
+    /// Reloads both internal reference points from BGxX and BGxY.
+    pub fn latch(&mut self) {
+        self.ref_x = sign_extend(self.params.x);
+        self.ref_y = sign_extend(self.params.y);
+    }
+
+    /// Moves the internal reference points to the next line.
+    pub fn step(&mut self) {
+        self.ref_x = self.ref_x.wrapping_add(self.params.pb as i16 as i32);
+        self.ref_y = self.ref_y.wrapping_add(self.params.pd as i16 as i32);
+    }
+
+    /// Maps a pixel of the line `dy` lines away from the current one.
+    pub fn map(&self, x: i32, dy: i32) -> (u16, u16) {
+        let pa = self.params.pa as i16 as i32;
+        let pb = self.params.pb as i16 as i32;
+        let pc = self.params.pc as i16 as i32;
+        let pd = self.params.pd as i16 as i32;
+
+        let tx = self.ref_x.wrapping_add(x * pa).wrapping_add(dy * pb) >> 8;
+        let ty = self.ref_y.wrapping_add(x * pc).wrapping_add(dy * pd) >> 8;
+
+        (tx as u16, ty as u16)
+    }
 }
 
 impl Bus for Bgtrans {
     fn read_byte(&self, _address: u32) -> u8 {
-        0 // TODO: open bus
+        0 // write-only, read as open bus by `PpuRegister::read`
     }
 
     fn write_byte(&mut self, address: u32, value: u8) {
@@ -16,8 +54,19 @@ impl Bus for Bgtrans {
             2..=3 => self.params.pb.write_byte(address, value),
             4..=5 => self.params.pc.write_byte(address, value),
             6..=7 => self.params.pd.write_byte(address, value),
-            8..=11 => self.params.x.write_byte(address, value),
-            _ => self.params.y.write_byte(address, value),
+            8..=11 => {
+                self.params.x.write_byte(address, value);
+                self.ref_x = sign_extend(self.params.x);
+            }
+            _ => {
+                self.params.y.write_byte(address, value);
+                self.ref_y = sign_extend(self.params.y);
+            }
         }
     }
 }
+
+/// The reference point registers are 28 bits wide.
+fn sign_extend(value: u32) -> i32 {
+    ((value << 4) as i32) >> 4
+}
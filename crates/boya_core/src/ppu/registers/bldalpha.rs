@@ -1,10 +1,15 @@
-use crate::{ppu::pixel::Color15, utils::bitflags::Bitflag};
+use crate::{
+    ppu::pixel::Color15,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
 
 #[derive(Debug, Default)]
 pub struct Bldalpha {
     pub value: u16,
 }
 
+impl_savestate!(Bldalpha { value });
+
 impl Bldalpha {
     fn eva(&self) -> u16 {
         u16::min(16, self.value.get_bits(0, 4))
@@ -1,10 +1,15 @@
-use crate::{ppu::registers::dispcnt::Background, utils::bitflags::Bitflag};
+use crate::{
+    ppu::registers::dispcnt::Background,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
 
 #[derive(Debug, Default)]
 pub struct Bldcnt {
     pub value: u16,
 }
 
+impl_savestate!(Bldcnt { value });
+
 impl Bldcnt {
     pub fn is_bg_first_target(&self, bg: Background) -> bool {
         self.value.has(bg as u16)
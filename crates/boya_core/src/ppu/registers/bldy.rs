@@ -1,10 +1,15 @@
-use crate::{ppu::pixel::Color15, utils::bitflags::Bitflag};
+use crate::{
+    ppu::pixel::Color15,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
 
 #[derive(Debug, Default)]
 pub struct Bldy {
     pub value: u16,
 }
 
+impl_savestate!(Bldy { value });
+
 impl Bldy {
     fn evy(&self) -> u16 {
         u16::min(16, self.value.get_bits(0, 4))
@@ -1,10 +1,18 @@
-use crate::{ppu::registers::window::Window, utils::bitflags::Bitflag};
+use crate::{
+    ppu::registers::window::Window,
+    utils::{
+        bitflags::Bitflag,
+        savestate::{impl_savestate, impl_savestate_enum},
+    },
+};
 
 #[derive(Debug, Default)]
 pub struct Dispcnt {
     pub value: u16,
 }
 
+impl_savestate!(Dispcnt { value });
+
 // TODO: CGB mode (bit 3)
 impl Dispcnt {
     pub fn bg_mode(&self) -> BgMode {
@@ -102,6 +110,8 @@ pub enum Background {
     Bg3,
 }
 
+impl_savestate_enum!(Background { Bg0, Bg1, Bg2, Bg3 });
+
 impl From<TransBackground> for Background {
     fn from(value: TransBackground) -> Self {
         match value {
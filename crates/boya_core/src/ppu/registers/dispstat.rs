@@ -1,4 +1,4 @@
-use crate::utils::bitflags::Bitflag;
+use crate::utils::{bitflags::Bitflag, savestate::impl_savestate};
 
 #[derive(Debug, Default)]
 pub struct Dispstat {
@@ -6,6 +6,8 @@ pub struct Dispstat {
     pub vcount: u8,
 }
 
+impl_savestate!(Dispstat { flags, vcount });
+
 impl Dispstat {
     pub const VBLANK: u8 = 0;
     pub const HBLANK: u8 = 1;
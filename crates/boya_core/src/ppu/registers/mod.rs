@@ -12,6 +12,7 @@ use crate::{
         mosaic::Mosaic,
         window::{WinH, WinV, Winin, Winout},
     },
+    utils::savestate::impl_savestate,
 };
 
 pub mod bgcnt;
@@ -61,9 +62,29 @@ pub struct PpuRegister {
     pub bldy: Bldy,
 }
 
-impl Bus for PpuRegister {
-    fn read_byte(&self, address: u32) -> u8 {
-        match address % 0x0400_0000 {
+impl_savestate!(PpuRegister {
+    dispcnt,
+    greenswap,
+    dispstat,
+    vcount,
+    bgcnt,
+    bgofs,
+    bg2trans,
+    bg3trans,
+    winh,
+    winv,
+    winin,
+    winout,
+    mosaic,
+    bldcnt,
+    bldalpha,
+    bldy
+});
+
+impl PpuRegister {
+    /// Returns `None` for unused and write-only registers, which read as open bus.
+    pub fn read(&self, address: u32) -> Option<u8> {
+        let value = match address % 0x0400_0000 {
             0x000..=0x001 => self.dispcnt.value.read_byte(address),
             0x002..=0x003 => self.greenswap.read_byte(address),
             0x004 => self.dispstat.flags,
@@ -76,8 +97,17 @@ impl Bus for PpuRegister {
             0x048..=0x049 => self.winin.value.read_byte(address),
             0x04A..=0x04B => self.winout.value.read_byte(address),
             0x050..=0x051 => self.bldcnt.value.read_byte(address),
-            _ => 0, // TODO: open bus
-        }
+            0x052..=0x053 => self.bldalpha.value.read_byte(address),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+}
+
+impl Bus for PpuRegister {
+    fn read_byte(&self, address: u32) -> u8 {
+        self.read(address).unwrap_or_default()
     }
 
     fn write_byte(&mut self, address: u32, value: u8) {
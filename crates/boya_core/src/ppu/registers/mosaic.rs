@@ -1,10 +1,12 @@
-use crate::utils::bitflags::Bitflag;
+use crate::utils::{bitflags::Bitflag, savestate::impl_savestate};
 
 #[derive(Debug, Default)]
 pub struct Mosaic {
     pub value: u16,
 }
 
+impl_savestate!(Mosaic { value });
+
 impl Mosaic {
     pub fn bg_mosaic_hsize(&self) -> u16 {
         self.value.get_bits(0, 3)
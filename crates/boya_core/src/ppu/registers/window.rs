@@ -1,4 +1,7 @@
-use crate::{ppu::registers::dispcnt::Background, utils::bitflags::Bitflag};
+use crate::{
+    ppu::registers::dispcnt::Background,
+    utils::{bitflags::Bitflag, savestate::impl_savestate},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Window {
@@ -13,17 +16,23 @@ pub struct WinH {
     pub x2: u8,
 }
 
+impl_savestate!(WinH { x1, x2 });
+
 #[derive(Debug, Default)]
 pub struct WinV {
     pub y1: u8,
     pub y2: u8,
 }
 
+impl_savestate!(WinV { y1, y2 });
+
 #[derive(Debug, Default)]
 pub struct Winin {
     pub value: u16,
 }
 
+impl_savestate!(Winin { value });
+
 impl Winin {
     pub fn bg_enable(&self, win: Window, bg: Background) -> bool {
         match win {
@@ -55,6 +64,8 @@ pub struct Winout {
     pub value: u16,
 }
 
+impl_savestate!(Winout { value });
+
 impl Winout {
     pub fn bg_enable(&self, bg: Background) -> bool {
         self.value.has(bg as u16)
@@ -0,0 +1,260 @@
+use crate::ppu::{
+    LCD_WIDTH, Layer, Ppu,
+    object::ObjPixel,
+    pixel::{Color15, Color24, PixelContext},
+    registers::dispcnt::{Background, BgMode},
+};
+
+/// Layers of the current line, each rendered once before being composited.
+#[derive(Debug)]
+struct LineBuffer {
+    bg: [[Option<Color15>; LCD_WIDTH]; 4],
+    /// Object pixel seen by each BG priority level.
+    obj: [[Option<ObjPixel>; LCD_WIDTH]; 4],
+}
+
+impl Default for LineBuffer {
+    fn default() -> Self {
+        Self {
+            bg: [[None; LCD_WIDTH]; 4],
+            obj: [[None; LCD_WIDTH]; 4],
+        }
+    }
+}
+
+impl Ppu {
+    /// Renders the current line into the frame buffer, with the registers as they are at the
+    /// end of HDraw.
+    pub fn render_scanline(&mut self) {
+        let y = self.scanline as u16;
+        let mut line = LineBuffer::default();
+
+        for bg in self.pipeline.sorted_bg {
+            if self.registers.dispcnt.bg_enable(bg) {
+                self.render_bg_line(bg, y, &mut line.bg[bg.to_index()]);
+            }
+        }
+
+        if self.registers.dispcnt.obj_enable() {
+            self.render_obj_line(y, &mut line.obj);
+        }
+
+        // layers without any pixel on the line can be left out of the walk, there are at most
+        // 4 BGs, each with the object level in front of it, and the objects above the backdrop
+        let visible = |layer: &Layer| match layer {
+            Layer::Object { level } => line.obj[*level as usize].iter().any(Option::is_some),
+            Layer::Background { bg } => line.bg[bg.to_index()].iter().any(Option::is_some),
+        };
+        let mut layers = [Layer::Object { level: 3 }; 9];
+        let mut count = 0;
+
+        for layer in self.iter_layers().filter(visible) {
+            layers[count] = layer;
+            count += 1;
+        }
+
+        let layers = &layers[..count];
+
+        let start = y as usize * LCD_WIDTH * 4;
+
+        for x in 0..LCD_WIDTH {
+            let get_layer_result = |layer, ctx: &PixelContext| match layer {
+                Layer::Object { level } if self.window_obj_enable(ctx.window) => {
+                    let pixel = line.obj[level as usize][x]?;
+                    Some(self.get_obj_blend_target(pixel, ctx))
+                }
+                Layer::Background { bg } if self.window_bg_enable(ctx.window, bg) => {
+                    let pixel = line.bg[bg.to_index()][x]?;
+                    Some(self.get_bg_blend_target(pixel, bg, ctx))
+                }
+                _ => None,
+            };
+
+            let color15 = self.compose_pixel(x as u16, y, layers.iter().copied(), get_layer_result);
+            let color24 = Color24::from(color15);
+            let idx = start + x * 4;
+
+            self.frame_buffer[idx] = color24.r;
+            self.frame_buffer[idx + 1] = color24.g;
+            self.frame_buffer[idx + 2] = color24.b;
+        }
+    }
+
+    fn render_bg_line(&self, bg: Background, y: u16, buffer: &mut [Option<Color15>; LCD_WIDTH]) {
+        let (_, my) = self.get_bg_mosaic(0, y, bg);
+        let text = match (self.registers.dispcnt.bg_mode(), bg) {
+            (BgMode::Mode0, _) => true,
+            (BgMode::Mode1, Background::Bg0 | Background::Bg1) => true,
+            (BgMode::Mode1 | BgMode::Mode2, Background::Bg2) => false,
+            (BgMode::Mode2, Background::Bg3) => false,
+            (_, Background::Bg2) => false, // bitmap modes
+            _ => return,                   // not available in this mode
+        };
+
+        if text {
+            self.render_text_line(bg, my, buffer);
+        } else {
+            for (x, pixel) in buffer.iter_mut().enumerate() {
+                *pixel = self.get_bg_pixel(x as u16, my, bg);
+            }
+        }
+
+        if self.registers.bgcnt[bg.to_index()].mosaic_enabled() {
+            let width = self.registers.mosaic.bg_mosaic_hsize() as usize + 1;
+
+            for x in 0..LCD_WIDTH {
+                buffer[x] = buffer[x - x % width];
+            }
+        }
+    }
+
+    /// Decodes the screen entry and the tile row once per tile instead of once per pixel.
+    fn render_text_line(&self, bg: Background, y: u16, buffer: &mut [Option<Color15>; LCD_WIDTH]) {
+        let bgcnt = self.registers.bgcnt[bg.to_index()];
+        let (width, _) = bgcnt.screen_mode().text_size();
+        let (mut ox, oy) = self.get_text_offset(0, y, bg);
+        let mut x = 0;
+
+        while x < LCD_WIDTH {
+            let bg_screen = self.get_text_screen(bg, ox, oy);
+            let char_data = Self::get_bg_char_data(bgcnt, bgcnt.color_mode(), bg_screen);
+            let row = self.get_char_row(oy % 8, &char_data);
+            let skip = (ox % 8) as usize;
+            let count = (8 - skip).min(LCD_WIDTH - x);
+
+            buffer[x..x + count].copy_from_slice(&row[skip..skip + count]);
+            x += count;
+            ox = (ox + count as u16) % width;
+        }
+    }
+
+    /// A level only sees the objects until the first one behind it in the pool, so each pixel
+    /// is visible from the lowest priority of the objects up to it.
+    fn render_obj_line(&self, y: u16, buffer: &mut [[Option<ObjPixel>; LCD_WIDTH]; 4]) {
+        let mut level = 0;
+
+        for (obj, width) in self.pipeline.obj_pool.iter() {
+            let char_data = self.get_obj_char_data(obj);
+
+            level = level.max(obj.bg_priority() as usize);
+
+            for offset in 0..width {
+                let x = (obj.x() + offset) as usize & 0x1FF;
+
+                if x >= LCD_WIDTH || buffer[3][x].is_some() {
+                    continue;
+                }
+
+                if let Some(pixel) = self.get_obj_screen_pixel(x as u16, y, obj, &char_data) {
+                    for layer in &mut buffer[level..] {
+                        layer[x].get_or_insert(pixel);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bus::Bus,
+        ppu::{LCD_HEIGHT, LCD_WIDTH, Ppu, pixel::Color24},
+    };
+
+    /// Fills the PPU memory with noise, keeping the object attributes away from prohibited codes.
+    fn noise_ppu(seed: u32) -> Ppu {
+        let mut ppu = Ppu::default();
+        let mut state = seed;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        for byte in ppu.vram.iter_mut() {
+            *byte = next() as u8 & 0x77; // mostly transparent 4bpp pixels
+        }
+
+        for byte in ppu.palette.iter_mut() {
+            *byte = next() as u8;
+        }
+
+        for id in 0..128 {
+            let attr0 = (next() as u16 & 0x33FF) | (next() as u16 & 0x4000);
+
+            ppu.oam.write_hword(id * 8, attr0);
+            ppu.oam.write_hword(id * 8 + 2, next() as u16);
+            ppu.oam.write_hword(id * 8 + 4, next() as u16 & 0xFC7F); // tiles within OBJ VRAM
+            ppu.oam.write_hword(id * 8 + 6, next() as u16 % 0x200);
+        }
+
+        for (i, bgcnt) in ppu.registers.bgcnt.iter_mut().enumerate() {
+            bgcnt.value = next() as u16 & 0xFFCF | (i as u16 * 2) << 8;
+        }
+
+        for bgofs in ppu.registers.bgofs.iter_mut() {
+            bgofs.x = next() as u16 & 0x1FF;
+            bgofs.y = next() as u16 & 0x1FF;
+        }
+
+        ppu.registers.bg2trans.write_hword(0, 0x0F0); // PA
+        ppu.registers.bg2trans.write_hword(2, 0xFFC0); // PB
+        ppu.registers.bg2trans.write_word(8, 0x1234); // X
+        ppu.registers.bg3trans.write_hword(6, 0x180); // PD
+        ppu.registers.winh[0].x1 = 20;
+        ppu.registers.winh[0].x2 = 180;
+        ppu.registers.winv[0].y1 = 10;
+        ppu.registers.winv[0].y2 = 100;
+        ppu.registers.winin.value = next() as u16;
+        ppu.registers.winout.value = next() as u16;
+        ppu.registers.mosaic.value = next() as u16;
+        ppu.registers.bldcnt.value = next() as u16;
+        ppu.registers.bldalpha.value = 0x0A06;
+        ppu.registers.bldy.value = 0x8;
+        ppu
+    }
+
+    #[test]
+    fn test_scanline_renderer() {
+        for (seed, dispcnt) in [
+            (1, 0x9F40),
+            (2, 0x3F01),
+            (3, 0x7E02),
+            (4, 0x1443),
+            (5, 0xF454),
+        ] {
+            let mut ppu = noise_ppu(seed);
+
+            ppu.registers.dispcnt.value = dispcnt;
+            ppu.pipeline.window_enabled = ppu.has_active_win();
+            ppu.sort_bg();
+
+            for y in 0..LCD_HEIGHT {
+                ppu.scanline = y as u8;
+                ppu.step_mosaic();
+                ppu.load_obj_pool();
+                ppu.render_scanline();
+
+                let row = &ppu.get_frame_buffer()[y * LCD_WIDTH * 4..][..LCD_WIDTH * 4];
+
+                for (x, pixel) in row.chunks_exact(4).enumerate() {
+                    let color = Color24::from(ppu.get_pixel(x as u16, y as u16));
+
+                    assert_eq!(
+                        [color.r, color.g, color.b],
+                        pixel[..3],
+                        "({x}, {y}) in mode {}",
+                        dispcnt & 7
+                    );
+                }
+
+                ppu.registers.bg2trans.step();
+                ppu.registers.bg3trans.step();
+            }
+
+            assert!(ppu.get_frame_buffer().iter().any(|&byte| byte != 0));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    bus::{
+        SRAM_SIZE,
+        backup::{
+            eeprom::{EEPROM_SIZE, EEPROM_SMALL_SIZE},
+            flash::FLASH_BANK_SIZE,
+        },
+    },
+    rom::{GpioDevices, SaveType},
+};
+
+const NO_GPIO: GpioDevices = GpioDevices {
+    rtc: false,
+    solar: false,
+    gyro: false,
+    rumble: false,
+    tilt: false,
+};
+
+const RTC: GpioDevices = GpioDevices {
+    rtc: true,
+    ..NO_GPIO
+};
+
+/// Bus configuration of a known cartridge, overriding the heuristics run on the ROM contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameEntry {
+    pub game_code: [u8; 4],
+    pub save_type: SaveType,
+    /// `None` when the size can only be found out at runtime.
+    pub save_size: Option<usize>,
+    pub gpio: GpioDevices,
+    /// Whether the ROM is mirrored across the whole gamepak area.
+    pub mirror_rom: bool,
+    /// Address of the loop polling for an interrupt, where the CPU can be halted instead.
+    pub idle_loop: Option<u32>,
+}
+
+impl GameEntry {
+    const fn new(game_code: &[u8; 4], save_type: SaveType, save_size: usize) -> Self {
+        Self {
+            game_code: *game_code,
+            save_type,
+            save_size: Some(save_size),
+            gpio: NO_GPIO,
+            mirror_rom: false,
+            idle_loop: None,
+        }
+    }
+
+    const fn gpio(self, gpio: GpioDevices) -> Self {
+        Self { gpio, ..self }
+    }
+
+    const fn idle_loop(self, address: u32) -> Self {
+        Self {
+            idle_loop: Some(address),
+            ..self
+        }
+    }
+
+    /// Classic NES Series carts check that the ROM is mirrored to detect copies.
+    const fn nes_classic(game_code: &[u8; 4]) -> Self {
+        Self {
+            mirror_rom: true,
+            ..Self::new(game_code, SaveType::Eeprom, EEPROM_SMALL_SIZE)
+        }
+    }
+
+    pub fn lookup(game_code: &[u8]) -> Option<&'static Self> {
+        GAME_DATABASE
+            .iter()
+            .find(|entry| entry.game_code == game_code)
+    }
+
+    /// Looks the game code up, falling back to detection from the ROM contents for unknown titles.
+    pub fn detect(rom: &[u8]) -> Self {
+        let game_code = rom.get(0xAC..0xB0).unwrap_or_default();
+
+        if let Some(entry) = Self::lookup(game_code) {
+            return *entry;
+        }
+
+        Self {
+            game_code: game_code.try_into().unwrap_or_default(),
+            save_type: SaveType::detect(rom),
+            save_size: None,
+            gpio: GpioDevices::detect(rom),
+            mirror_rom: false,
+            idle_loop: None,
+        }
+    }
+}
+
+const FLASH_64K: usize = FLASH_BANK_SIZE;
+const FLASH_128K: usize = FLASH_BANK_SIZE * 2;
+
+#[rustfmt::skip]
+pub const GAME_DATABASE: &[GameEntry] = &[
+    // Advance Wars 1-2
+    GameEntry::new(b"AWRE", SaveType::Flash64K, FLASH_64K).idle_loop(0x0803_8810),
+    GameEntry::new(b"AWRP", SaveType::Flash64K, FLASH_64K).idle_loop(0x0803_8810),
+    GameEntry::new(b"AW2E", SaveType::Flash64K, FLASH_64K).idle_loop(0x0803_66E4),
+    GameEntry::new(b"AW2P", SaveType::Flash64K, FLASH_64K).idle_loop(0x0803_6E18),
+    // Final Fantasy Tactics Advance
+    GameEntry::new(b"AFXE", SaveType::Flash64K, FLASH_64K).idle_loop(0x0800_0428),
+    // Mega Man Battle Network
+    GameEntry::new(b"AREE", SaveType::Sram, SRAM_SIZE).idle_loop(0x0800_032E),
+    // Pokémon Ruby, Sapphire, Emerald, FireRed and LeafGreen
+    GameEntry::new(b"AXVE", SaveType::Flash128K, FLASH_128K).gpio(RTC),
+    GameEntry::new(b"AXVJ", SaveType::Flash128K, FLASH_128K).gpio(RTC),
+    GameEntry::new(b"AXPE", SaveType::Flash128K, FLASH_128K).gpio(RTC),
+    GameEntry::new(b"AXPJ", SaveType::Flash128K, FLASH_128K).gpio(RTC),
+    GameEntry::new(b"BPEE", SaveType::Flash128K, FLASH_128K).gpio(RTC).idle_loop(0x0800_08C6),
+    GameEntry::new(b"BPEJ", SaveType::Flash128K, FLASH_128K).gpio(RTC),
+    GameEntry::new(b"BPRE", SaveType::Flash128K, FLASH_128K),
+    GameEntry::new(b"BPGE", SaveType::Flash128K, FLASH_128K),
+    // Rockman EXE 4.5 and Sennen Kazoku
+    GameEntry::new(b"BR4J", SaveType::Flash128K, FLASH_128K).gpio(RTC),
+    GameEntry::new(b"BKAJ", SaveType::Flash128K, FLASH_128K).gpio(RTC),
+    // Super Mario Advance 4
+    GameEntry::new(b"AX4E", SaveType::Flash128K, FLASH_128K),
+    GameEntry::new(b"AX4P", SaveType::Flash128K, FLASH_128K),
+    // Boktai 1-3
+    GameEntry::new(b"U3IE", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { solar: true, ..RTC }),
+    GameEntry::new(b"U3IJ", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { solar: true, ..RTC }),
+    GameEntry::new(b"U3IP", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { solar: true, ..RTC }),
+    GameEntry::new(b"U32E", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { solar: true, ..RTC }),
+    GameEntry::new(b"U32J", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { solar: true, ..RTC }),
+    GameEntry::new(b"U33J", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { solar: true, ..RTC }),
+    // WarioWare Twisted and Drill Dozer
+    GameEntry::new(b"RZWE", SaveType::Sram, SRAM_SIZE).gpio(GpioDevices { gyro: true, rumble: true, ..NO_GPIO }),
+    GameEntry::new(b"RZWJ", SaveType::Sram, SRAM_SIZE).gpio(GpioDevices { gyro: true, rumble: true, ..NO_GPIO }),
+    GameEntry::new(b"V49E", SaveType::Sram, SRAM_SIZE).gpio(GpioDevices { rumble: true, ..NO_GPIO }),
+    GameEntry::new(b"V49J", SaveType::Sram, SRAM_SIZE).gpio(GpioDevices { rumble: true, ..NO_GPIO }),
+    // Yoshi Topsy-Turvy and Koro Koro Puzzle
+    GameEntry::new(b"KYGE", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { tilt: true, ..NO_GPIO }),
+    GameEntry::new(b"KYGJ", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { tilt: true, ..NO_GPIO }),
+    GameEntry::new(b"KYGP", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { tilt: true, ..NO_GPIO }),
+    GameEntry::new(b"KHPJ", SaveType::Eeprom, EEPROM_SIZE).gpio(GpioDevices { tilt: true, ..NO_GPIO }),
+    // Classic NES Series
+    GameEntry::nes_classic(b"FBME"), // Bomberman
+    GameEntry::nes_classic(b"FADE"), // Castlevania
+    GameEntry::nes_classic(b"FDKE"), // Donkey Kong
+    GameEntry::nes_classic(b"FDME"), // Dr. Mario
+    GameEntry::nes_classic(b"FEBE"), // Excitebike
+    GameEntry::nes_classic(b"FICE"), // Ice Climber
+    GameEntry::nes_classic(b"FLBE"), // The Legend of Zelda
+    GameEntry::nes_classic(b"FMRE"), // Metroid
+    GameEntry::nes_classic(b"FP7E"), // Pac-Man
+    GameEntry::nes_classic(b"FSME"), // Super Mario Bros.
+    GameEntry::nes_classic(b"FXVE"), // Xevious
+    GameEntry::nes_classic(b"FZLE"), // Zelda II: The Adventure of Link
+];
@@ -1,6 +1,11 @@
-use std::error::Error;
+pub mod database;
+
+use std::{error::Error, fmt::Display};
+
+use crate::rom::database::GAME_DATABASE;
 
 pub const HEADER_SIZE: usize = 192;
+pub const MAX_ROM_SIZE: usize = 0x0200_0000; // 32mb
 
 pub const NINTENDO_LOGO: [u8; 156] = [
     0x24, 0xFF, 0xAE, 0x51, 0x69, 0x9A, 0xA2, 0x21, 0x3D, 0x84, 0x82, 0x0A, 0x84, 0xE4, 0x09, 0xAD,
@@ -15,6 +20,97 @@ pub const NINTENDO_LOGO: [u8; 156] = [
     0xD6, 0x25, 0xE4, 0x8B, 0x38, 0x0A, 0xAC, 0x72, 0x21, 0xD4, 0xF8, 0x07,
 ];
 
+#[derive(Debug, PartialEq)]
+pub enum RomError {
+    BadLogo,
+    BadFixedValue,
+    BadChecksum,
+    TooLarge(usize),
+    Truncated,
+}
+
+impl Display for RomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadLogo => write!(f, "invalid Nintendo logo"),
+            Self::BadFixedValue => write!(f, "invalid fixed value"),
+            Self::BadChecksum => write!(f, "bad header checksum"),
+            Self::TooLarge(size) => write!(f, "ROM is too large ({size} bytes)"),
+            Self::Truncated => write!(f, "ROM is shorter than its header"),
+        }
+    }
+}
+
+impl Error for RomError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaveType {
+    #[default]
+    Sram,
+    Flash64K,
+    Flash128K,
+    Eeprom,
+}
+
+impl SaveType {
+    /// Library ID strings embedded by the Nintendo SDK backup libraries
+    const LIBRARY_IDS: [(&[u8], SaveType); 5] = [
+        (b"EEPROM_V", SaveType::Eeprom),
+        (b"SRAM_V", SaveType::Sram),
+        (b"FLASH_V", SaveType::Flash64K),
+        (b"FLASH512_V", SaveType::Flash64K),
+        (b"FLASH1M_V", SaveType::Flash128K),
+    ];
+
+    /// Scans the ROM for a word aligned library ID, defaulting to SRAM.
+    pub fn detect(rom: &[u8]) -> Self {
+        (0..rom.len())
+            .step_by(4)
+            .find_map(|offset| {
+                Self::LIBRARY_IDS
+                    .iter()
+                    .find(|(id, _)| rom[offset..].starts_with(id))
+                    .map(|(_, save_type)| *save_type)
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Peripherals wired to the cartridge GPIO port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GpioDevices {
+    pub rtc: bool,
+    pub solar: bool,
+    pub gyro: bool,
+    pub rumble: bool,
+    /// Accelerometer in the backup area, not on the GPIO port itself.
+    pub tilt: bool,
+}
+
+impl GpioDevices {
+    /// Library ID of the Nintendo SDK RTC driver.
+    const RTC_LIBRARY_ID: &[u8] = b"SIIRTC_V";
+
+    /// Borrows the wiring of another release of the game from the database, as the region letter
+    /// doesn't change the cartridge, falling back to the RTC library ID for unknown titles.
+    pub fn detect(rom: &[u8]) -> Self {
+        let code = rom.get(0xAC..0xAF).unwrap_or_default();
+
+        GAME_DATABASE
+            .iter()
+            .find(|entry| entry.game_code[..3] == *code)
+            .map_or_else(
+                || Self {
+                    rtc: (0..rom.len())
+                        .step_by(4)
+                        .any(|offset| rom[offset..].starts_with(Self::RTC_LIBRARY_ID)),
+                    ..Self::default()
+                },
+                |entry| entry.gpio,
+            )
+    }
+}
+
 #[derive(Debug)]
 pub struct CartridgeHeader {
     pub entry_point: u32,
@@ -25,22 +121,37 @@ pub struct CartridgeHeader {
     pub checksum: u8,
 }
 
+impl CartridgeHeader {
+    pub fn parse(rom: &[u8]) -> Result<Self, RomError> {
+        let header: [u8; HEADER_SIZE] = rom
+            .get(..HEADER_SIZE)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(RomError::Truncated)?;
+
+        Self::try_from(header)
+    }
+}
+
 impl TryFrom<[u8; HEADER_SIZE]> for CartridgeHeader {
-    type Error = Box<dyn Error>;
+    type Error = RomError;
 
     fn try_from(value: [u8; HEADER_SIZE]) -> Result<Self, Self::Error> {
-        let entry_point = u32::from_le_bytes(value[0..4].try_into()?);
+        let entry_point = u32::from_le_bytes([value[0], value[1], value[2], value[3]]);
 
         if value[4..160] != NINTENDO_LOGO {
-            return Err("Invalid Logo".into());
+            return Err(RomError::BadLogo);
         }
 
-        let title = value[0xA0..0xAC].try_into()?;
-        let game_code = value[0xAC..0xB0].try_into()?;
-        let maker_code = value[0xB0..0xB2].try_into()?;
+        let mut title = [0; 12];
+        let mut game_code = [0; 4];
+        let mut maker_code = [0; 2];
+
+        title.copy_from_slice(&value[0xA0..0xAC]);
+        game_code.copy_from_slice(&value[0xAC..0xB0]);
+        maker_code.copy_from_slice(&value[0xB0..0xB2]);
 
         if value[0xB2] != 0x96 {
-            return Err("Invalid Fixed Value".into());
+            return Err(RomError::BadFixedValue);
         }
 
         let software_version = value[0xBC];
@@ -53,7 +164,7 @@ impl TryFrom<[u8; HEADER_SIZE]> for CartridgeHeader {
         }
 
         if calc_check.wrapping_sub(0x19) != checksum {
-            return Err("Bad checksum".into());
+            return Err(RomError::BadChecksum);
         }
 
         Ok(Self {
@@ -66,3 +177,138 @@ impl TryFrom<[u8; HEADER_SIZE]> for CartridgeHeader {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::rom::{
+        CartridgeHeader, GpioDevices, HEADER_SIZE, NINTENDO_LOGO, RomError, SaveType,
+        database::GameEntry,
+    };
+
+    fn header(game_code: &[u8; 4]) -> Vec<u8> {
+        let mut rom = vec![0; HEADER_SIZE];
+
+        rom[4..160].copy_from_slice(&NINTENDO_LOGO);
+        rom[0xAC..0xB0].copy_from_slice(game_code);
+        rom[0xB2] = 0x96;
+        rom[0xBD] = rom[0xA0..=0xBC]
+            .iter()
+            .fold(0u8, |check, byte| check.wrapping_sub(*byte))
+            .wrapping_sub(0x19);
+        rom
+    }
+
+    #[test]
+    fn test_header_parsing() {
+        let mut rom = header(b"FSME");
+
+        let parsed = CartridgeHeader::parse(&rom).unwrap();
+        assert_eq!(&parsed.game_code, b"FSME");
+
+        assert_eq!(
+            CartridgeHeader::parse(&rom[..0xA0]).unwrap_err(),
+            RomError::Truncated
+        );
+
+        rom[0xBD] ^= 1;
+        assert_eq!(
+            CartridgeHeader::parse(&rom).unwrap_err(),
+            RomError::BadChecksum
+        );
+
+        rom[0xB2] = 0;
+        assert_eq!(
+            CartridgeHeader::parse(&rom).unwrap_err(),
+            RomError::BadFixedValue
+        );
+
+        rom[4] = 0;
+        assert_eq!(CartridgeHeader::parse(&rom).unwrap_err(), RomError::BadLogo);
+    }
+
+    #[test]
+    fn test_game_database() {
+        let entry = GameEntry::detect(&header(b"FSME"));
+
+        assert_eq!(entry.save_type, SaveType::Eeprom);
+        assert_eq!(entry.save_size, Some(0x200));
+        assert!(entry.mirror_rom);
+
+        let entry = GameEntry::detect(&header(b"BPEE"));
+
+        assert_eq!(entry.save_type, SaveType::Flash128K);
+        assert!(entry.gpio.rtc);
+        assert_eq!(entry.idle_loop, Some(0x0800_08C6));
+
+        let mut rom = header(b"ABCD");
+
+        rom.extend_from_slice(b"EEPROM_V");
+
+        let entry = GameEntry::detect(&rom);
+
+        assert_eq!(entry.save_type, SaveType::Eeprom, "detected from the ROM");
+        assert_eq!(entry.save_size, None);
+        assert!(!entry.mirror_rom);
+    }
+
+    #[test]
+    fn test_save_type_detection() {
+        let mut rom = vec![0; 0x100];
+
+        assert_eq!(SaveType::detect(&rom), SaveType::Sram);
+
+        rom[0xC1..0xCA].copy_from_slice(b"FLASH1M_V");
+        assert_eq!(SaveType::detect(&rom), SaveType::Sram, "unaligned ID");
+
+        rom[0xC0..0xCA].copy_from_slice(b"FLASH512_V");
+        assert_eq!(SaveType::detect(&rom), SaveType::Flash64K);
+
+        rom[0xC0..0xC9].copy_from_slice(b"FLASH1M_V");
+        assert_eq!(SaveType::detect(&rom), SaveType::Flash128K);
+
+        rom[0x40..0x48].copy_from_slice(b"EEPROM_V");
+        assert_eq!(SaveType::detect(&rom), SaveType::Eeprom);
+    }
+
+    #[test]
+    fn test_gpio_detection() {
+        let mut rom = vec![0; 0x200];
+
+        let rtc = GpioDevices {
+            rtc: true,
+            ..Default::default()
+        };
+
+        assert_eq!(GpioDevices::detect(&rom), GpioDevices::default());
+
+        rom[0xAC..0xB0].copy_from_slice(b"BPEE");
+        assert_eq!(GpioDevices::detect(&rom), rtc);
+
+        rom[0xAC..0xB0].copy_from_slice(b"U3IJ");
+        assert_eq!(
+            GpioDevices::detect(&rom),
+            GpioDevices { solar: true, ..rtc }
+        );
+
+        rom[0xAC..0xB0].copy_from_slice(b"RZWE");
+        assert_eq!(
+            GpioDevices::detect(&rom),
+            GpioDevices {
+                gyro: true,
+                rumble: true,
+                ..Default::default()
+            }
+        );
+
+        rom[0xAC..0xB0].copy_from_slice(b"U32P");
+        assert_eq!(
+            GpioDevices::detect(&rom),
+            GpioDevices { solar: true, ..rtc },
+            "other region"
+        );
+
+        rom[0xAC..0xB0].copy_from_slice(b"ABCD");
+        rom[0x100..0x108].copy_from_slice(b"SIIRTC_V");
+        assert_eq!(GpioDevices::detect(&rom), rtc);
+    }
+}
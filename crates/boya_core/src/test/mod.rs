@@ -233,7 +233,7 @@ impl GbaTestBuilder {
         let mut gba = Gba::default();
 
         gba.load_bios(self.init_bios());
-        gba.load_rom(&self.init_rom());
+        gba.load_rom(&self.init_rom()).unwrap();
         gba.boot();
 
         let extra_steps = if self.thumb { 9 } else { 4 };
@@ -52,9 +52,9 @@ where
     }
 
     fn take(&mut self, bit: Self) -> bool {
-        let res = !(T::from(1) << bit);
-        *self &= res;
-        res != T::from(0)
+        let value = self.has(bit);
+        self.clear(bit);
+        value
     }
 
     fn set_bits(&mut self, start: T, end: T, value: T) {
@@ -1,3 +1,5 @@
+use crate::utils::savestate::{Savestate, StateError, StateReader, StateWriter};
+
 #[derive(Debug)]
 pub struct FifoBuffer<T, const L: usize> {
     items: [T; L],
@@ -11,6 +13,10 @@ where
     T: Default + Copy,
 {
     pub fn push(&mut self, value: T) {
+        if self.len == L {
+            return;
+        }
+
         self.items[self.tail] = value;
         self.tail = (self.tail + 1) % L;
         self.len += 1;
@@ -55,3 +61,28 @@ where
         }
     }
 }
+
+impl<T, const L: usize> Savestate for FifoBuffer<T, L>
+where
+    T: Default + Copy + Savestate,
+{
+    fn save_state(&self, writer: &mut StateWriter) {
+        self.items.save_state(writer);
+        self.head.save_state(writer);
+        self.tail.save_state(writer);
+        self.len.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        self.items.load_state(reader)?;
+        self.head.load_state(reader)?;
+        self.tail.load_state(reader)?;
+        self.len.load_state(reader)?;
+
+        if self.head >= L || self.tail >= L || self.len > L {
+            return Err(StateError::InvalidValue("FifoBuffer"));
+        }
+
+        Ok(())
+    }
+}
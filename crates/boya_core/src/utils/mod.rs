@@ -1,6 +1,8 @@
 pub mod bitflags;
 pub mod collections;
 pub mod ops;
+pub mod rewind;
+pub mod savestate;
 
 pub trait Reset {
     fn reset(&mut self);
@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+
+pub const DEFAULT_REWIND_CAPACITY: usize = 600;
+pub const DEFAULT_REWIND_INTERVAL: u32 = 1;
+
+/// Ring buffer of save states, each one stored as a delta against the state captured after it.
+///
+/// Only the newest snapshot is kept in full, stepping back XORs it with the most recent delta.
+/// A snapshot that differs too much to shrink as a delta is stored as is.
+#[derive(Debug)]
+pub struct Rewind {
+    enabled: bool,
+    capacity: usize,
+    interval: u32,
+    frame_count: u32,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Snapshot>,
+}
+
+#[derive(Debug)]
+enum Snapshot {
+    Delta(Vec<u8>),
+    Full(Vec<u8>),
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: DEFAULT_REWIND_CAPACITY,
+            interval: DEFAULT_REWIND_INTERVAL,
+            frame_count: 0,
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+}
+
+impl Rewind {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Keeps up to `capacity` snapshots, captured every `interval` frames. A capacity of 0 disables
+    /// rewind.
+    pub fn configure(&mut self, capacity: usize, interval: u32) {
+        self.enabled = capacity > 0;
+        self.capacity = capacity;
+        self.interval = interval.max(1);
+        self.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.frame_count = 0;
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// Number of snapshots that can be restored.
+    pub fn len(&self) -> usize {
+        self.latest.as_ref().map_or(0, |_| self.deltas.len() + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Counts a completed frame, returns whether a snapshot is due.
+    pub fn tick_frame(&mut self) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        self.frame_count += 1;
+
+        if self.frame_count < self.interval {
+            return false;
+        }
+
+        self.frame_count = 0;
+        true
+    }
+
+    pub fn push(&mut self, state: Vec<u8>) {
+        if let Some(prev) = self.latest.take() {
+            let delta = encode_delta(&prev, &state);
+
+            self.deltas.push_back(match delta.len() < prev.len() {
+                true => Snapshot::Delta(delta),
+                false => Snapshot::Full(prev),
+            });
+        }
+
+        self.latest = Some(state);
+
+        while self.len() > self.capacity {
+            self.deltas.pop_front();
+        }
+    }
+
+    /// Drops the newest snapshot and returns the one captured before it.
+    pub fn pop(&mut self) -> Option<&[u8]> {
+        let snapshot = self.deltas.pop_back()?;
+        let latest = self.latest.as_mut()?;
+
+        match snapshot {
+            Snapshot::Delta(delta) => decode_delta(latest, &delta),
+            Snapshot::Full(prev) => *latest = prev,
+        }
+
+        self.frame_count = 0;
+        self.latest.as_deref()
+    }
+
+    /// Newest snapshot, without removing it.
+    pub fn peek(&self) -> Option<&[u8]> {
+        self.latest.as_deref()
+    }
+}
+
+/// Encodes `prev ^ next` as runs of `(zero count, literal count, literals)` prefixed with the
+/// length of `prev`.
+fn encode_delta(prev: &[u8], next: &[u8]) -> Vec<u8> {
+    let len = prev.len().max(next.len());
+    let xor = (0..len).map(|i| prev.get(i).unwrap_or(&0) ^ next.get(i).unwrap_or(&0));
+    let xor = xor.collect::<Vec<_>>();
+    let mut delta = (prev.len() as u32).to_le_bytes().to_vec();
+    let mut i = 0;
+
+    while i < len {
+        let zeros = xor[i..].iter().take_while(|&&b| b == 0).count();
+        let start = i + zeros;
+        let literals = xor[start..].iter().take_while(|&&b| b != 0).count();
+
+        delta.extend_from_slice(&(zeros as u32).to_le_bytes());
+        delta.extend_from_slice(&(literals as u32).to_le_bytes());
+        delta.extend_from_slice(&xor[start..start + literals]);
+        i = start + literals;
+    }
+
+    delta
+}
+
+/// Turns `next` back into `prev` in place.
+fn decode_delta(state: &mut Vec<u8>, delta: &[u8]) {
+    let read_u32 =
+        |offset: usize| u32::from_le_bytes(delta[offset..offset + 4].try_into().unwrap()) as usize;
+    let prev_len = read_u32(0);
+    let mut offset = 4;
+    let mut i = 0;
+
+    state.resize(state.len().max(prev_len), 0);
+
+    while offset < delta.len() {
+        let zeros = read_u32(offset);
+        let literals = read_u32(offset + 4);
+        let xor = &delta[offset + 8..offset + 8 + literals];
+
+        i += zeros;
+        state[i..i + literals]
+            .iter_mut()
+            .zip(xor)
+            .for_each(|(byte, xor)| *byte ^= xor);
+
+        i += literals;
+        offset += 8 + literals;
+    }
+
+    state.truncate(prev_len);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::rewind::{Rewind, Snapshot};
+
+    #[test]
+    fn test_rewind_ring() {
+        let mut rewind = Rewind::default();
+        let states = [
+            vec![1, 2, 3, 4],
+            vec![1, 2, 0, 4, 5],
+            vec![9, 2, 0],
+            vec![9, 2, 0],
+        ];
+
+        rewind.configure(3, 2);
+
+        for state in &states {
+            for _ in 0..2 {
+                if rewind.tick_frame() {
+                    rewind.push(state.clone());
+                }
+            }
+        }
+
+        assert_eq!(rewind.len(), 3, "oldest snapshot dropped");
+        assert_eq!(rewind.peek(), Some(&states[3][..]));
+        assert_eq!(rewind.pop(), Some(&states[2][..]));
+        assert_eq!(rewind.pop(), Some(&states[1][..]));
+        assert_eq!(rewind.pop(), None);
+        assert_eq!(rewind.len(), 1);
+    }
+
+    #[test]
+    fn test_rewind_full_snapshot() {
+        let mut rewind = Rewind::default();
+        let mut states = vec![vec![0; 64], vec![1; 64], vec![2; 64]];
+
+        states.push(states[2].clone());
+        states[3][10] = 3;
+        rewind.configure(4, 1);
+
+        for state in &states {
+            rewind.tick_frame();
+            rewind.push(state.clone());
+        }
+
+        assert!(
+            matches!(rewind.deltas[0], Snapshot::Full(_)),
+            "delta too long"
+        );
+        assert!(matches!(rewind.deltas[1], Snapshot::Full(_)));
+        assert!(
+            matches!(rewind.deltas[2], Snapshot::Delta(_)),
+            "one byte changed"
+        );
+
+        assert_eq!(rewind.pop(), Some(&states[2][..]));
+        assert_eq!(rewind.pop(), Some(&states[1][..]));
+        assert_eq!(rewind.pop(), Some(&states[0][..]));
+    }
+}
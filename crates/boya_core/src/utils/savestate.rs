@@ -0,0 +1,310 @@
+use std::{error::Error, fmt::Display};
+
+pub const STATE_MAGIC: [u8; 4] = *b"BOYA";
+/// Bumped whenever the layout of a saved component changes
+pub const STATE_VERSION: u16 = 15;
+
+#[derive(Debug, PartialEq)]
+pub enum StateError {
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    InvalidValue(&'static str),
+    UnexpectedEof,
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "not a save state"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported save state version {v}"),
+            Self::InvalidValue(name) => write!(f, "invalid value for {name}"),
+            Self::UnexpectedEof => write!(f, "save state is truncated"),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+/// Machine state that can be written to and restored from a save state.
+///
+/// Fields are stored in declaration order as little-endian values, without any padding.
+pub trait Savestate {
+    fn save_state(&self, writer: &mut StateWriter);
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError>;
+}
+
+#[derive(Debug, Default)]
+pub struct StateWriter {
+    buffer: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        let mut writer = Self::default();
+
+        writer.write_bytes(&STATE_MAGIC);
+        STATE_VERSION.save_state(&mut writer);
+        writer
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+#[derive(Debug)]
+pub struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, StateError> {
+        let mut reader = Self { data };
+        let mut version = 0u16;
+
+        if reader.read_bytes(STATE_MAGIC.len())? != STATE_MAGIC {
+            return Err(StateError::InvalidMagic);
+        }
+
+        version.load_state(&mut reader)?;
+
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        Ok(reader)
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.data.len() < len {
+            return Err(StateError::UnexpectedEof);
+        }
+
+        let (bytes, rest) = self.data.split_at(len);
+
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        let mut array = [0; N];
+
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+}
+
+macro_rules! impl_savestate_int {
+    ($($ty:ty),*) => {
+        $(
+            impl Savestate for $ty {
+                fn save_state(&self, writer: &mut StateWriter) {
+                    writer.write_bytes(&self.to_le_bytes());
+                }
+
+                fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+                    *self = <$ty>::from_le_bytes(reader.read_array()?);
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_savestate_int!(u8, u16, u32, u64, i8, i16, i32);
+
+impl Savestate for usize {
+    fn save_state(&self, writer: &mut StateWriter) {
+        (*self as u64).save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        let mut value = 0u64;
+
+        value.load_state(reader)?;
+        *self = value as usize;
+        Ok(())
+    }
+}
+
+impl Savestate for bool {
+    fn save_state(&self, writer: &mut StateWriter) {
+        (*self as u8).save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        let mut value = 0u8;
+
+        value.load_state(reader)?;
+        *self = value != 0;
+        Ok(())
+    }
+}
+
+impl<T: Savestate, const N: usize> Savestate for [T; N] {
+    fn save_state(&self, writer: &mut StateWriter) {
+        self.iter().for_each(|item| item.save_state(writer));
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        self.iter_mut().try_for_each(|item| item.load_state(reader))
+    }
+}
+
+impl<T: Savestate + ?Sized> Savestate for Box<T> {
+    fn save_state(&self, writer: &mut StateWriter) {
+        self.as_ref().save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        self.as_mut().load_state(reader)
+    }
+}
+
+/// Variable length buffers are prefixed with their length.
+impl Savestate for Vec<u8> {
+    fn save_state(&self, writer: &mut StateWriter) {
+        self.len().save_state(writer);
+        writer.write_bytes(self);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        let mut len = 0usize;
+
+        len.load_state(reader)?;
+        *self = reader.read_bytes(len)?.to_vec();
+        Ok(())
+    }
+}
+
+impl<T: Savestate + Default> Savestate for Option<T> {
+    fn save_state(&self, writer: &mut StateWriter) {
+        self.is_some().save_state(writer);
+
+        if let Some(value) = self {
+            value.save_state(writer);
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        let mut some = false;
+
+        some.load_state(reader)?;
+
+        *self = match some {
+            true => {
+                let mut value = T::default();
+                value.load_state(reader)?;
+                Some(value)
+            }
+            false => None,
+        };
+
+        Ok(())
+    }
+}
+
+/// Implements `Savestate` by saving the listed fields in order.
+///
+/// Indices that are used for slicing are range-checked after loading with an optional
+/// `if |value| ...` predicate, so that a corrupt state is rejected instead of panicking later.
+macro_rules! impl_savestate {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        $crate::utils::savestate::impl_savestate!($ty { $($field),* } if |_| true);
+    };
+    ($ty:ty { $($field:ident),* $(,)? } if $valid:expr) => {
+        impl $crate::utils::savestate::Savestate for $ty {
+            fn save_state(&self, writer: &mut $crate::utils::savestate::StateWriter) {
+                $($crate::utils::savestate::Savestate::save_state(&self.$field, writer);)*
+            }
+
+            fn load_state(
+                &mut self,
+                reader: &mut $crate::utils::savestate::StateReader,
+            ) -> Result<(), $crate::utils::savestate::StateError> {
+                let valid: fn(&Self) -> bool = $valid;
+
+                $($crate::utils::savestate::Savestate::load_state(&mut self.$field, reader)?;)*
+
+                match valid(self) {
+                    true => Ok(()),
+                    false => Err($crate::utils::savestate::StateError::InvalidValue(stringify!($ty))),
+                }
+            }
+        }
+    };
+}
+
+/// Implements `Savestate` for a fieldless enum, stored as its variant index.
+macro_rules! impl_savestate_enum {
+    ($ty:ident { $($variant:ident),* $(,)? }) => {
+        impl $crate::utils::savestate::Savestate for $ty {
+            fn save_state(&self, writer: &mut $crate::utils::savestate::StateWriter) {
+                $crate::utils::savestate::Savestate::save_state(&(*self as u8), writer);
+            }
+
+            fn load_state(
+                &mut self,
+                reader: &mut $crate::utils::savestate::StateReader,
+            ) -> Result<(), $crate::utils::savestate::StateError> {
+                let mut index = 0u8;
+
+                $crate::utils::savestate::Savestate::load_state(&mut index, reader)?;
+
+                *self = [$($ty::$variant),*]
+                    .into_iter()
+                    .nth(index as usize)
+                    .ok_or($crate::utils::savestate::StateError::InvalidValue(stringify!($ty)))?;
+
+                Ok(())
+            }
+        }
+    };
+}
+
+pub(crate) use impl_savestate;
+pub(crate) use impl_savestate_enum;
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::savestate::{STATE_VERSION, Savestate, StateError, StateReader, StateWriter};
+
+    #[test]
+    fn test_savestate_header() {
+        let mut writer = StateWriter::new();
+
+        0x1234u16.save_state(&mut writer);
+        Some(vec![1u8, 2, 3]).save_state(&mut writer);
+
+        let data = writer.finish();
+        let mut reader = StateReader::new(&data).unwrap();
+        let mut value = 0u16;
+        let mut buffer: Option<Vec<u8>> = None;
+
+        value.load_state(&mut reader).unwrap();
+        buffer.load_state(&mut reader).unwrap();
+
+        assert_eq!(value, 0x1234);
+        assert_eq!(buffer, Some(vec![1, 2, 3]));
+        assert_eq!(
+            value.load_state(&mut reader),
+            Err(StateError::UnexpectedEof)
+        );
+
+        let mut data = data;
+        data[4] = STATE_VERSION as u8 + 1;
+
+        assert!(matches!(
+            StateReader::new(&data),
+            Err(StateError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(
+            StateReader::new(b"GBA!"),
+            Err(StateError::InvalidMagic)
+        ));
+    }
+}
@@ -1,11 +1,26 @@
 pub mod types;
 
-use boya_core::{Gba as GbaCore, bus::Bus, ppu::pixel::Color24, rom::HEADER_SIZE, utils::Reset};
+use boya_core::{
+    Gba as GbaCore,
+    bus::{Bus, gpio::rtc::RtcClock},
+    ppu::pixel::Color24,
+    utils::Reset,
+};
 use wasm_bindgen::prelude::*;
-use web_sys::js_sys::{Uint8Array, Uint32Array};
+use web_sys::js_sys::{Date, Uint8Array, Uint32Array};
 
 use crate::types::{Background, CartridgeHeader, ColorMode, IOMap, MemoryRegion, Obj};
 
+/// `SystemTime` is unavailable in the browser, the RTC reads `Date.now()` instead.
+#[derive(Debug)]
+struct JsClock;
+
+impl RtcClock for JsClock {
+    fn now(&self) -> u64 {
+        (Date::now() / 1000.0) as u64
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Default)]
 pub struct Gba {
@@ -16,7 +31,10 @@ pub struct Gba {
 impl Gba {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-        Self::default()
+        let mut gba = Self::default();
+
+        gba.core.set_rtc_clock(JsClock);
+        gba
     }
 
     #[wasm_bindgen(js_name = "loadBios")]
@@ -25,9 +43,55 @@ impl Gba {
         Ok(())
     }
 
+    #[wasm_bindgen(js_name = "setHleBios")]
+    pub fn set_hle_bios(&mut self, enabled: bool) {
+        self.core.set_hle_bios(enabled);
+    }
+
     #[wasm_bindgen(js_name = "loadRom")]
-    pub fn load_rom(&mut self, rom: &[u8]) {
-        self.core.load_rom(rom);
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), JsError> {
+        self.core.load_rom(rom)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = "setRomMirroring")]
+    pub fn set_rom_mirroring(&mut self, enabled: bool) {
+        self.core.set_rom_mirroring(enabled);
+    }
+
+    #[wasm_bindgen(js_name = "loadSave")]
+    pub fn load_save(&mut self, data: &[u8]) {
+        self.core.load_save(data);
+    }
+
+    #[wasm_bindgen(js_name = "saveData")]
+    pub fn save_data(&self) -> Vec<u8> {
+        self.core.save_data()
+    }
+
+    #[wasm_bindgen(js_name = "pollSaveDirty")]
+    pub fn poll_save_dirty(&mut self) -> bool {
+        self.core.poll_save_dirty()
+    }
+
+    #[wasm_bindgen(js_name = "saveState")]
+    pub fn save_state(&self) -> Vec<u8> {
+        self.core.save_state()
+    }
+
+    #[wasm_bindgen(js_name = "loadState")]
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), JsError> {
+        Ok(self.core.load_state(data)?)
+    }
+
+    #[wasm_bindgen(js_name = "setRewind")]
+    pub fn set_rewind(&mut self, capacity: usize, interval: u32) {
+        self.core.set_rewind(capacity, interval);
+    }
+
+    #[wasm_bindgen(js_name = "stepBack")]
+    pub fn step_back(&mut self) -> bool {
+        self.core.step_back()
     }
 
     #[wasm_bindgen]
@@ -118,6 +182,48 @@ impl Gba {
         self.core.set_keyinput(value);
     }
 
+    #[wasm_bindgen(js_name = "setSolarLevel")]
+    pub fn set_solar_level(&mut self, level: u8) {
+        self.core.set_solar_level(level);
+    }
+
+    #[wasm_bindgen(js_name = "setGyro")]
+    pub fn set_gyro(&mut self, rate: i16) {
+        self.core.set_gyro(rate);
+    }
+
+    #[wasm_bindgen(js_name = "setTilt")]
+    pub fn set_tilt(&mut self, x: i16, y: i16) {
+        self.core.set_tilt(x, y);
+    }
+
+    pub fn rumble(&self) -> bool {
+        self.core.rumble()
+    }
+
+    #[wasm_bindgen(js_name = "setSampleRate")]
+    pub fn set_sample_rate(&mut self, rate: u32) {
+        self.core.set_sample_rate(rate);
+    }
+
+    #[wasm_bindgen(js_name = "pendingSamples")]
+    pub fn pending_samples(&self) -> usize {
+        self.core.pending_samples()
+    }
+
+    /// Fills `buffer` with interleaved stereo samples in the -1.0..1.0 range expected by WebAudio.
+    #[wasm_bindgen(js_name = "drainSamples")]
+    pub fn drain_samples(&mut self, buffer: &mut [f32]) -> usize {
+        let mut samples = vec![0; buffer.len()];
+        let written = self.core.drain_samples(&mut samples);
+
+        for (dst, src) in buffer.iter_mut().zip(&samples[..written]) {
+            *dst = *src as f32 / 32768.0;
+        }
+
+        written
+    }
+
     #[wasm_bindgen(js_name = "getRegionSlice")]
     pub fn get_region_slice(
         &self,
@@ -251,12 +357,7 @@ impl Gba {
 
     #[wasm_bindgen(js_name = "parseHeader")]
     pub fn parse_header(&mut self, rom: &[u8]) -> Result<JsValue, JsError> {
-        let bytes: [u8; HEADER_SIZE] = rom
-            .get(..HEADER_SIZE)
-            .ok_or(JsError::new("No header found"))?
-            .try_into()?;
-        let raw_header = boya_core::rom::CartridgeHeader::try_from(bytes)
-            .map_err(|err| JsError::new(&err.to_string()))?;
+        let raw_header = boya_core::rom::CartridgeHeader::parse(rom)?;
         let header = CartridgeHeader::from(raw_header);
 
         Ok(serde_wasm_bindgen::to_value(&header)?)